@@ -0,0 +1,88 @@
+use std::fmt::Display;
+
+/// The kind of account a statement is for. Bank accounts take it from the ACCTTYPE element of
+/// BANKACCTFROM, credit card statements are always `CreditCard`.
+///
+/// Types that are not in the OFX spec are kept in `Other` along with the value from the file.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AccountType {
+    Checking,
+    Savings,
+    /// Money market
+    MoneyMrkt,
+    /// Line of credit
+    CreditLine,
+    /// Certificate of deposit
+    Cd,
+    CreditCard,
+    Investment,
+    Other(String),
+}
+
+impl AccountType {
+    /// The value of the type as written in an OFX file. Eg. MONEYMRKT
+    pub fn as_str(&self) -> &str {
+        match self {
+            AccountType::Checking => "CHECKING",
+            AccountType::Savings => "SAVINGS",
+            AccountType::MoneyMrkt => "MONEYMRKT",
+            AccountType::CreditLine => "CREDITLINE",
+            AccountType::Cd => "CD",
+            AccountType::CreditCard => "CREDITCARD",
+            AccountType::Investment => "INVESTMENT",
+            AccountType::Other(value) => value,
+        }
+    }
+}
+
+impl From<&str> for AccountType {
+    fn from(value: &str) -> Self {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "CHECKING" => AccountType::Checking,
+            "SAVINGS" => AccountType::Savings,
+            "MONEYMRKT" => AccountType::MoneyMrkt,
+            "CREDITLINE" => AccountType::CreditLine,
+            "CD" => AccountType::Cd,
+            "CREDITCARD" => AccountType::CreditCard,
+            "INVESTMENT" => AccountType::Investment,
+            _ => AccountType::Other(value.to_string()),
+        }
+    }
+}
+
+impl Display for AccountType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod account_type_tests {
+    use super::*;
+
+    #[test]
+    fn test_account_type_round_trip() {
+        let types = [
+            "CHECKING",
+            "SAVINGS",
+            "MONEYMRKT",
+            "CREDITLINE",
+            "CD",
+            "CREDITCARD",
+            "INVESTMENT",
+        ];
+        for value in types {
+            let acct_type = AccountType::from(value);
+            assert!(!matches!(acct_type, AccountType::Other(_)));
+            assert_eq!(acct_type.to_string(), value);
+        }
+        assert_eq!(AccountType::from("savings"), AccountType::Savings);
+    }
+
+    #[test]
+    fn test_account_type_unknown() {
+        let acct_type = AccountType::from("BROKERAGE");
+        assert_eq!(acct_type, AccountType::Other("BROKERAGE".to_string()));
+        assert_eq!(acct_type.as_str(), "BROKERAGE");
+    }
+}
@@ -0,0 +1,458 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The most digits an amount can have after the decimal point.
+const MAX_SCALE: u32 = 28;
+
+/// An exact decimal amount of money, as found in TRNAMT and BALAMT.
+///
+/// The amount is stored as an integer number of the smallest unit written in the file along
+/// with the number of digits after the decimal point, so `-55.75` is `-5575` with a scale of 2.
+/// The scale the file used is kept and shows up again when the amount is displayed. Amounts
+/// with different scales that have the same value compare as equal.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Amount {
+    mantissa: i128,
+    scale: u32,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseAmountError {
+    #[error("the amount is empty")]
+    Empty,
+    #[error("the amount does not have any digits")]
+    NoDigits,
+    #[error("'{0}' is not allowed in an amount")]
+    InvalidCharacter(char),
+    #[error("exponents are not allowed in an amount")]
+    Exponent,
+    #[error("the amount has more than {MAX_SCALE} digits after the decimal point")]
+    TooPrecise,
+    #[error("the amount is too large")]
+    Overflow,
+}
+
+impl Amount {
+    pub const ZERO: Amount = Amount {
+        mantissa: 0,
+        scale: 0,
+    };
+
+    /// Creates the amount `mantissa * 10^-scale`. Eg. `Amount::new(-5575, 2)` is -55.75.
+    ///
+    /// Panics if the scale is larger than 28.
+    pub fn new(mantissa: i128, scale: u32) -> Self {
+        assert!(
+            scale <= MAX_SCALE,
+            "The scale of an amount can be at most {}",
+            MAX_SCALE
+        );
+        Amount { mantissa, scale }
+    }
+
+    /// The amount as an integer number of its smallest unit. Eg. -5575 for -55.75.
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    /// The number of digits after the decimal point.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mantissa < 0
+    }
+
+    /// The same amount written with `scale` digits after the decimal point. Returns None if
+    /// digits would be lost or the amount would not fit.
+    pub fn with_scale(&self, scale: u32) -> Option<Self> {
+        if scale > MAX_SCALE {
+            return None;
+        }
+        let mantissa = if scale >= self.scale {
+            self.mantissa.checked_mul(10i128.pow(scale - self.scale))?
+        } else {
+            let divisor = 10i128.pow(self.scale - scale);
+            if self.mantissa % divisor != 0 {
+                return None;
+            }
+            self.mantissa / divisor
+        };
+        Some(Amount { mantissa, scale })
+    }
+
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let (a, b) = self.aligned(other)?;
+        Some(Amount {
+            mantissa: a.mantissa.checked_add(b.mantissa)?,
+            scale: a.scale,
+        })
+    }
+
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        let (a, b) = self.aligned(other)?;
+        Some(Amount {
+            mantissa: a.mantissa.checked_sub(b.mantissa)?,
+            scale: a.scale,
+        })
+    }
+
+    pub fn checked_neg(&self) -> Option<Self> {
+        Some(Amount {
+            mantissa: self.mantissa.checked_neg()?,
+            scale: self.scale,
+        })
+    }
+
+    /// Multiplies two amounts. The scale of the result is the sum of both scales.
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let scale = self.scale + other.scale;
+        if scale > MAX_SCALE {
+            return None;
+        }
+        Some(Amount {
+            mantissa: self.mantissa.checked_mul(other.mantissa)?,
+            scale,
+        })
+    }
+
+    /// Divides by `other` and rounds the result half away from zero to `scale` digits after the
+    /// decimal point. Returns None when `other` is zero or the result would not fit.
+    pub fn checked_div(&self, other: &Self, scale: u32) -> Option<Self> {
+        if other.is_zero() || scale > MAX_SCALE {
+            return None;
+        }
+        // One extra digit is worked out so that the result can be rounded
+        let exponent = i64::from(scale) + 1 + i64::from(other.scale) - i64::from(self.scale);
+        let power = 10i128.checked_pow(u32::try_from(exponent.abs()).ok()?)?;
+        let (numerator, denominator) = if exponent >= 0 {
+            (self.mantissa.checked_mul(power)?, other.mantissa)
+        } else {
+            (self.mantissa, other.mantissa.checked_mul(power)?)
+        };
+        Some(Amount {
+            mantissa: round_last_digit(numerator.checked_div(denominator)?),
+            scale,
+        })
+    }
+
+    /// The amount rounded half away from zero to `scale` digits after the decimal point.
+    /// Returns None if the amount would not fit.
+    pub fn checked_round(&self, scale: u32) -> Option<Self> {
+        if scale >= self.scale {
+            return self.with_scale(scale);
+        }
+        Some(Amount {
+            mantissa: round_last_digit(self.mantissa / 10i128.pow(self.scale - scale - 1)),
+            scale,
+        })
+    }
+
+    /// Adds up all the amounts. Returns None if the total does not fit.
+    pub fn checked_sum<'a>(amounts: impl IntoIterator<Item = &'a Amount>) -> Option<Self> {
+        amounts
+            .into_iter()
+            .try_fold(Amount::ZERO, |total, x| total.checked_add(x))
+    }
+
+    // Both amounts written with the larger of the two scales
+    fn aligned(&self, other: &Self) -> Option<(Self, Self)> {
+        let scale = self.scale.max(other.scale);
+        Some((self.with_scale(scale)?, other.with_scale(scale)?))
+    }
+
+    // The same value without trailing zeros after the decimal point
+    fn normalized(&self) -> Self {
+        let mut normalized = *self;
+        while normalized.scale > 0 && normalized.mantissa % 10 == 0 {
+            normalized.mantissa /= 10;
+            normalized.scale -= 1;
+        }
+        normalized
+    }
+}
+
+// Drops the last digit of the mantissa, rounding half away from zero
+fn round_last_digit(mantissa: i128) -> i128 {
+    let carry = if (mantissa % 10).abs() >= 5 {
+        mantissa.signum()
+    } else {
+        0
+    };
+    mantissa / 10 + carry
+}
+
+impl FromStr for Amount {
+    type Err = ParseAmountError;
+
+    /// Parses an amount like `-55.75`, `+1000.00` or `.5`. OFX allows a comma to be used as
+    /// the decimal point, so `-55,75` is read as well.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(ParseAmountError::Empty);
+        }
+        let (negative, digits) = match value.as_bytes()[0] {
+            b'-' => (true, &value[1..]),
+            b'+' => (false, &value[1..]),
+            _ => (false, value),
+        };
+
+        let mut mantissa: i128 = 0;
+        let mut scale = None;
+        let mut seen_digit = false;
+        for x in digits.chars() {
+            match x {
+                '0'..='9' => {
+                    seen_digit = true;
+                    mantissa = mantissa
+                        .checked_mul(10)
+                        .and_then(|m| m.checked_add(i128::from(x as u8 - b'0')))
+                        .ok_or(ParseAmountError::Overflow)?;
+                    if let Some(scale) = scale.as_mut() {
+                        *scale += 1;
+                        if *scale > MAX_SCALE {
+                            return Err(ParseAmountError::TooPrecise);
+                        }
+                    }
+                }
+                '.' | ',' if scale.is_none() => scale = Some(0),
+                'e' | 'E' if seen_digit => return Err(ParseAmountError::Exponent),
+                _ => return Err(ParseAmountError::InvalidCharacter(x)),
+            }
+        }
+        if !seen_digit {
+            return Err(ParseAmountError::NoDigits);
+        }
+
+        Ok(Amount {
+            mantissa: if negative { -mantissa } else { mantissa },
+            scale: scale.unwrap_or(0),
+        })
+    }
+}
+
+impl Display for Amount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    }
+}
+
+impl PartialEq for Amount {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Amount {}
+
+impl PartialOrd for Amount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Amount {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // The whole parts are compared first so that rescaling can never overflow. What is
+        // left over after the decimal point always fits once written with the larger scale.
+        let whole = |x: &Amount| x.mantissa / 10i128.pow(x.scale);
+        let fraction = |x: &Amount, scale: u32| {
+            (x.mantissa % 10i128.pow(x.scale)) * 10i128.pow(scale - x.scale)
+        };
+        let scale = self.scale.max(other.scale);
+        whole(self)
+            .cmp(&whole(other))
+            .then_with(|| fraction(self, scale).cmp(&fraction(other, scale)))
+    }
+}
+
+impl Hash for Amount {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let normalized = self.normalized();
+        normalized.mantissa.hash(state);
+        normalized.scale.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod amount_tests {
+    use super::*;
+
+    #[test]
+    fn test_amount_parse() {
+        let amount: Amount = "-55.75".parse().unwrap();
+        assert_eq!(amount.mantissa(), -5575);
+        assert_eq!(amount.scale(), 2);
+
+        let amount: Amount = "+1000.00".parse().unwrap();
+        assert_eq!(amount.mantissa(), 100000);
+        assert_eq!(amount.scale(), 2);
+
+        assert_eq!("5".parse::<Amount>().unwrap(), Amount::new(5, 0));
+        assert_eq!(".5".parse::<Amount>().unwrap(), Amount::new(5, 1));
+        assert_eq!("-55,75".parse::<Amount>().unwrap(), Amount::new(-5575, 2));
+        assert_eq!(" 12.30 ".parse::<Amount>().unwrap().scale(), 2);
+    }
+
+    #[test]
+    fn test_amount_parse_invalid() {
+        assert_eq!("".parse::<Amount>(), Err(ParseAmountError::Empty));
+        assert_eq!("-".parse::<Amount>(), Err(ParseAmountError::NoDigits));
+        assert_eq!(".".parse::<Amount>(), Err(ParseAmountError::NoDigits));
+        assert_eq!("1e5".parse::<Amount>(), Err(ParseAmountError::Exponent));
+        assert_eq!("1.5E-3".parse::<Amount>(), Err(ParseAmountError::Exponent));
+        assert_eq!(
+            "NaN".parse::<Amount>(),
+            Err(ParseAmountError::InvalidCharacter('N'))
+        );
+        assert_eq!(
+            "inf".parse::<Amount>(),
+            Err(ParseAmountError::InvalidCharacter('i'))
+        );
+        assert_eq!(
+            "1.2.3".parse::<Amount>(),
+            Err(ParseAmountError::InvalidCharacter('.'))
+        );
+        assert_eq!(
+            "--1".parse::<Amount>(),
+            Err(ParseAmountError::InvalidCharacter('-'))
+        );
+        assert_eq!(
+            "1,000.00".parse::<Amount>(),
+            Err(ParseAmountError::InvalidCharacter('.'))
+        );
+        assert_eq!(
+            "0.00000000000000000000000000001".parse::<Amount>(),
+            Err(ParseAmountError::TooPrecise)
+        );
+        assert_eq!(
+            "1000000000000000000000000000000000000000".parse::<Amount>(),
+            Err(ParseAmountError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_amount_display_keeps_scale() {
+        for value in ["-55.75", "1000.00", "0.05", "-0.5", "7", "0.000"] {
+            assert_eq!(value.parse::<Amount>().unwrap().to_string(), value);
+        }
+        assert_eq!("+3.10".parse::<Amount>().unwrap().to_string(), "3.10");
+        assert_eq!(".5".parse::<Amount>().unwrap().to_string(), "0.5");
+    }
+
+    #[test]
+    fn test_amount_comparison() {
+        assert_eq!(Amount::new(100000, 2), Amount::new(1000, 0));
+        assert_eq!(Amount::new(-50, 2), Amount::new(-5, 1));
+        assert!(Amount::new(-5575, 2) < Amount::new(-55, 0));
+        assert!(Amount::new(1, 28) > Amount::ZERO);
+        assert!(Amount::new(i128::MAX, 0) > Amount::new(i128::MAX, 28));
+
+        let mut hasher_a = std::collections::hash_map::DefaultHasher::new();
+        let mut hasher_b = std::collections::hash_map::DefaultHasher::new();
+        Amount::new(1050, 2).hash(&mut hasher_a);
+        Amount::new(105, 1).hash(&mut hasher_b);
+        assert_eq!(hasher_a.finish(), hasher_b.finish());
+    }
+
+    #[test]
+    fn test_amount_checked_arithmetic() {
+        let a: Amount = "0.10".parse().unwrap();
+        let b: Amount = "0.2".parse().unwrap();
+        let sum = a.checked_add(&b).unwrap();
+        assert_eq!(sum.to_string(), "0.30");
+        assert_eq!(a.checked_sub(&b).unwrap().to_string(), "-0.10");
+        assert_eq!(a.checked_neg().unwrap().to_string(), "-0.10");
+        assert_eq!(a.checked_mul(&b).unwrap().to_string(), "0.020");
+
+        let amounts: Vec<Amount> = ["0.10"; 10].iter().map(|x| x.parse().unwrap()).collect();
+        assert_eq!(Amount::checked_sum(&amounts).unwrap().to_string(), "1.00");
+
+        let max = Amount::new(i128::MAX, 0);
+        assert!(max.checked_add(&Amount::new(1, 0)).is_none());
+        assert!(max.checked_add(&Amount::new(1, 2)).is_none());
+        assert!(Amount::new(i128::MIN, 0).checked_neg().is_none());
+        assert!(
+            Amount::new(1, 20)
+                .checked_mul(&Amount::new(1, 20))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_amount_checked_div() {
+        let amount: Amount = "100.00".parse().unwrap();
+        let rate: Amount = "1.0850".parse().unwrap();
+        assert_eq!(amount.checked_div(&rate, 2).unwrap().to_string(), "92.17");
+        assert_eq!(
+            Amount::new(-1, 0)
+                .checked_div(&Amount::new(3, 0), 4)
+                .unwrap()
+                .to_string(),
+            "-0.3333"
+        );
+        assert_eq!(
+            Amount::new(5, 2)
+                .checked_div(&Amount::new(2, 0), 2)
+                .unwrap()
+                .to_string(),
+            "0.03"
+        );
+        assert_eq!(
+            Amount::new(-5, 2)
+                .checked_div(&Amount::new(2, 0), 2)
+                .unwrap()
+                .to_string(),
+            "-0.03"
+        );
+        assert!(amount.checked_div(&Amount::ZERO, 2).is_none());
+        assert!(
+            Amount::new(i128::MAX, 0)
+                .checked_div(&Amount::new(1, 0), 2)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_amount_checked_round() {
+        let amount: Amount = "92.1659".parse().unwrap();
+        assert_eq!(amount.checked_round(2).unwrap().to_string(), "92.17");
+        assert_eq!(amount.checked_round(0).unwrap().to_string(), "92");
+        assert_eq!(amount.checked_round(6).unwrap().to_string(), "92.165900");
+        assert_eq!(
+            Amount::new(-125, 3).checked_round(2).unwrap().to_string(),
+            "-0.13"
+        );
+        assert_eq!(
+            Amount::new(-124, 3).checked_round(2).unwrap().to_string(),
+            "-0.12"
+        );
+    }
+
+    #[test]
+    fn test_amount_with_scale() {
+        let amount = Amount::new(-5575, 2);
+        assert_eq!(amount.with_scale(4).unwrap().mantissa(), -557500);
+        assert_eq!(amount.with_scale(1), None);
+        assert_eq!(Amount::new(1000, 2).with_scale(0).unwrap().mantissa(), 10);
+        assert_eq!(amount.with_scale(MAX_SCALE + 1), None);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::Amount;
+use crate::BalanceType;
+use crate::Currency;
+use crate::OfxDateTime;
+use crate::Parseable;
+use crate::QFXParsingError;
+use crate::lexer::TokenKind;
+use crate::lexer::TokenStream;
+
+/// A BAL of a BALLIST. These are the balances a financial institution sends on top of the
+/// LEDGERBAL and AVAILBAL. Eg. the minimum payment due on a credit card.
+#[derive(Clone, Debug)]
+pub struct Bal {
+    pub name: String,
+    pub desc: String,
+    pub bal_type: BalanceType,
+    pub value: Amount,
+    pub dt_as_of: Option<OfxDateTime>,
+    /// Set when the value is in a currency other than the CURDEF of the statement
+    pub currency: Option<Currency>,
+}
+
+// The names financial institutions commonly use for the balances that have a lookup in
+// `BalList`. Names are compared with only their letters and digits, ignoring case.
+const MINIMUM_PAYMENT_DUE: [&str; 4] = [
+    "MINIMUMPAYMENTDUE",
+    "MINIMUMPAYMENT",
+    "MINPAYMENTDUE",
+    "MINPMTDUE",
+];
+const CREDIT_LIMIT: [&str; 3] = ["CREDITLIMIT", "CREDITLINE", "CREDITLMT"];
+const STATEMENT_BALANCE: [&str; 3] = ["STATEMENTBALANCE", "STMTBALANCE", "STMTBAL"];
+const INTEREST_YTD: [&str; 4] = ["INTERESTYTD", "YTDINTEREST", "INTERESTPAIDYTD", "INTYTD"];
+
+/// Lookups for the balances of a BALLIST. Implemented for the `ballist` of both bank and credit
+/// card statements. Eg. `ccstmtrs.ballist.credit_limit()`
+pub trait BalList {
+    /// The balance with the given NAME or DESC. Case, spaces and punctuation are ignored, so
+    /// "Credit limit" finds a balance named CREDITLIMIT.
+    fn balance(&self, name: &str) -> Option<&Bal>;
+    fn minimum_payment_due(&self) -> Option<&Bal>;
+    fn credit_limit(&self) -> Option<&Bal>;
+    fn statement_balance(&self) -> Option<&Bal>;
+    fn interest_ytd(&self) -> Option<&Bal>;
+}
+
+impl BalList for [Bal] {
+    fn balance(&self, name: &str) -> Option<&Bal> {
+        find_balance(self, &[name])
+    }
+
+    fn minimum_payment_due(&self) -> Option<&Bal> {
+        find_balance(self, &MINIMUM_PAYMENT_DUE)
+    }
+
+    fn credit_limit(&self) -> Option<&Bal> {
+        find_balance(self, &CREDIT_LIMIT)
+    }
+
+    fn statement_balance(&self) -> Option<&Bal> {
+        find_balance(self, &STATEMENT_BALANCE)
+    }
+
+    fn interest_ytd(&self) -> Option<&Bal> {
+        find_balance(self, &INTEREST_YTD)
+    }
+}
+
+// Finds the first balance whose NAME or DESC is one of the given names
+fn find_balance<'a>(ballist: &'a [Bal], names: &[&str]) -> Option<&'a Bal> {
+    let normalize = |x: &str| -> String {
+        x.chars()
+            .filter(|x| x.is_alphanumeric())
+            .flat_map(char::to_uppercase)
+            .collect()
+    };
+    let names: Vec<String> = names.iter().map(|x| normalize(x)).collect();
+    ballist
+        .iter()
+        .find(|bal| names.contains(&normalize(&bal.name)) || names.contains(&normalize(&bal.desc)))
+}
+
+impl<'a> Parseable<'a> for Bal {
+    const TAGS: &'static [&'static str] =
+        &["NAME", "DESC", "BALTYPE", "VALUE", "DTASOF", "CURRENCY"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
+        let mut s_name = None;
+        let mut s_desc = None;
+        let mut s_bal_type = None;
+        let mut s_value = None;
+        let mut s_dt_as_of = None;
+        let mut s_currency = None;
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("NAME") => {
+                    s_name = Some(tokens.value("NAME")?);
+                }
+                TokenKind::StartTag("DESC") => {
+                    s_desc = Some(tokens.value("DESC")?);
+                }
+                TokenKind::StartTag("BALTYPE") => {
+                    s_bal_type = Some(BalanceType::from(tokens.value("BALTYPE")?.as_str()));
+                }
+                TokenKind::StartTag("VALUE") => {
+                    let value = tokens.value("VALUE")?;
+                    s_value = Some(value.parse::<Amount>().map_err(|e| {
+                        QFXParsingError::invalid_amount(
+                            format!("Invalid balance value {} with {}", value, e),
+                            &value,
+                        )
+                    })?);
+                }
+                TokenKind::StartTag("DTASOF") => {
+                    s_dt_as_of = Some(tokens.datetime("DTASOF")?);
+                }
+                TokenKind::StartTag("CURRENCY") => {
+                    s_currency = Some(Currency::parse(tokens)?);
+                }
+                TokenKind::EndTag("BAL") => {
+                    return Ok(Self {
+                        name: s_name.ok_or(QFXParsingError::missing_value(
+                            "Missing NAME in BAL".to_string(),
+                            "NAME",
+                        ))?,
+                        desc: s_desc.ok_or(QFXParsingError::missing_value(
+                            "Missing DESC in BAL".to_string(),
+                            "DESC",
+                        ))?,
+                        bal_type: s_bal_type.ok_or(QFXParsingError::missing_value(
+                            "Missing BALTYPE in BAL".to_string(),
+                            "BALTYPE",
+                        ))?,
+                        value: s_value.ok_or(QFXParsingError::missing_value(
+                            "Missing VALUE in BAL".to_string(),
+                            "VALUE",
+                        ))?,
+                        dt_as_of: s_dt_as_of,
+                        currency: s_currency,
+                    });
+                }
+                _ => {
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the BAL type", token),
+                            token,
+                            Self::TAGS,
+                            "BAL",
+                        ),
+                    )?;
+                }
+            }
+        }
+        Err(QFXParsingError::unexpected_eof(
+            "Found unexpected EOF. Was still expecting the '/BAL' token".to_string(),
+            "/BAL",
+        ))
+    }
+}
+
+// The tags a BALLIST can contain. It is parsed by parse_ballist as it has no type of its own
+pub(crate) const BALLIST_TAGS: &[&str] = &["BAL"];
+
+// Parses the BAL aggregates of a BALLIST up to the closing '/BALLIST'
+pub(crate) fn parse_ballist(tokens: &mut TokenStream) -> Result<Vec<Bal>, QFXParsingError> {
+    let mut ballist = vec![];
+    while let Some(token) = tokens.next_token()? {
+        match token.kind {
+            TokenKind::StartTag("BAL") => {
+                ballist.push(Bal::parse(tokens)?);
+            }
+            TokenKind::EndTag("BALLIST") => {
+                return Ok(ballist);
+            }
+            _ => {
+                // Unknown token seen. This is an error unless unknown tags are being skipped
+                tokens.skip_unknown(
+                    token,
+                    QFXParsingError::unknown_token(
+                        format!("Found unexpected token {} in the BALLIST type", token),
+                        token,
+                        BALLIST_TAGS,
+                        "BALLIST",
+                    ),
+                )?;
+            }
+        }
+    }
+    Err(QFXParsingError::unexpected_eof(
+        "Found unexpected EOF. Was still expecting the '/BALLIST' token".to_string(),
+        "/BALLIST",
+    ))
+}
+
+#[cfg(test)]
+mod balance_tests {
+    use super::*;
+    use crate::tokenize;
+
+    fn bal(name: &str, desc: &str) -> Bal {
+        Bal {
+            name: name.to_string(),
+            desc: desc.to_string(),
+            bal_type: BalanceType::Dollar,
+            value: Amount::ZERO,
+            dt_as_of: None,
+            currency: None,
+        }
+    }
+
+    #[test]
+    fn test_bal_list_lookups() {
+        let ballist = [
+            bal("MINPMTDUE", "Minimum payment"),
+            bal("LIMIT", "Credit Limit"),
+            bal("Statement balance", "Balance on the statement"),
+            bal("YTD-INTEREST", "Interest"),
+        ];
+        assert_eq!(ballist.minimum_payment_due().unwrap().name, "MINPMTDUE");
+        assert_eq!(ballist.credit_limit().unwrap().name, "LIMIT");
+        assert_eq!(
+            ballist.statement_balance().unwrap().name,
+            "Statement balance"
+        );
+        assert_eq!(ballist.interest_ytd().unwrap().name, "YTD-INTEREST");
+        assert_eq!(ballist.balance("limit").unwrap().desc, "Credit Limit");
+        assert!(ballist.balance("APR").is_none());
+        assert!(Vec::<Bal>::new().credit_limit().is_none());
+    }
+
+    #[test]
+    fn test_bal_missing_name() {
+        let mut tokens = tokenize("<DESC>Credit limit<BALTYPE>DOLLAR<VALUE>1</BAL>");
+        assert!(matches!(
+            Bal::parse(&mut tokens),
+            Err(QFXParsingError::MissingRequiredValue { field, .. }) if field == "NAME"
+        ));
+    }
+}
@@ -0,0 +1,61 @@
+use std::fmt::Display;
+
+/// How the VALUE of a BAL in a BALLIST should be read, from its BALTYPE element.
+///
+/// Types that are not in the OFX spec are kept in `Other` along with the value from the file.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BalanceType {
+    /// An amount of money
+    Dollar,
+    /// A percentage. Eg. an interest rate
+    Percent,
+    /// A plain number
+    Number,
+    Other(String),
+}
+
+impl BalanceType {
+    /// The value of the type as written in an OFX file. Eg. PERCENT
+    pub fn as_str(&self) -> &str {
+        match self {
+            BalanceType::Dollar => "DOLLAR",
+            BalanceType::Percent => "PERCENT",
+            BalanceType::Number => "NUMBER",
+            BalanceType::Other(value) => value,
+        }
+    }
+}
+
+impl From<&str> for BalanceType {
+    fn from(value: &str) -> Self {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "DOLLAR" => BalanceType::Dollar,
+            "PERCENT" => BalanceType::Percent,
+            "NUMBER" => BalanceType::Number,
+            _ => BalanceType::Other(value.to_string()),
+        }
+    }
+}
+
+impl Display for BalanceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod balance_type_tests {
+    use super::*;
+
+    #[test]
+    fn test_balance_type_from_str() {
+        assert_eq!(BalanceType::from("DOLLAR"), BalanceType::Dollar);
+        assert_eq!(BalanceType::from("percent"), BalanceType::Percent);
+        assert_eq!(BalanceType::from(" NUMBER "), BalanceType::Number);
+        assert_eq!(
+            BalanceType::from("POINTS"),
+            BalanceType::Other("POINTS".to_string())
+        );
+        assert_eq!(BalanceType::Percent.to_string(), "PERCENT");
+    }
+}
@@ -1,20 +1,25 @@
+use crate::AccountType;
 use crate::AvailableBalance;
+use crate::Bal;
 use crate::BankTranList;
 use crate::LedgerBal;
 use crate::Parseable;
 use crate::QFXParsingError;
 use crate::Status;
+use crate::balance::parse_ballist;
+use crate::lexer::TokenKind;
+use crate::lexer::TokenStream;
 
 #[derive(Clone)]
 pub struct BankMsgSrsV1 {
-    pub stmttrns: Stmttrnrs, // TODO: This should be a vector, there can be more than 1 of these, but there should be at least 1
+    pub stmttrns: Vec<Stmttrnrs>, // There has to be at least 1 of these
 }
 
 #[derive(Clone)]
 pub struct Stmttrnrs {
     pub trnuid: Option<String>,
     pub status: Option<Status>,
-    pub stmtrs: Stmtrs,
+    pub stmtrs: Option<Stmtrs>, // Missing when the STATUS is an error
 }
 
 #[derive(Clone)]
@@ -24,209 +29,256 @@ pub struct Stmtrs {
     pub banktranslist: BankTranList,
     pub ledgerbal: Option<LedgerBal>,
     pub availbal: Option<AvailableBalance>,
+    pub ballist: Vec<Bal>, // Empty when there is no BALLIST. See BalList for lookups
+    /// Marketing text from the bank
+    pub mktginfo: Option<String>,
 }
 
-#[derive(Clone)]
+/// A bank account. Used for BANKACCTFROM and for the BANKACCTTO of a transfer.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Bankacctfrom {
     pub acct_id: String,
-    pub acct_type: String,
+    pub acct_type: AccountType,
     pub bank_id: Option<String>,
 }
 
 impl<'a> Parseable<'a> for BankMsgSrsV1 {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
-        let mut s_stmttrns = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "STMTTRNRS" => {
-                    s_stmttrns = Some(Stmttrnrs::parse(tokens)?);
+    const TAGS: &'static [&'static str] = &["STMTTRNRS"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
+        let mut s_stmttrns: Vec<Stmttrnrs> = vec![];
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("STMTTRNRS") => {
+                    s_stmttrns.push(Stmttrnrs::parse(tokens)?);
                 }
-                "/BANKMSGSRSV1" => {
-                    return Ok(Self {
-                        stmttrns: s_stmttrns.ok_or(QFXParsingError::MissingRequiredValue(
+                TokenKind::EndTag("BANKMSGSRSV1") => {
+                    if s_stmttrns.is_empty() {
+                        return Err(QFXParsingError::missing_value(
                             "STMTTRNRS is a required value in BANKMSGSRSV1".to_string(),
-                        ))?,
+                            "STMTTRNRS",
+                        ));
+                    }
+                    return Ok(Self {
+                        stmttrns: s_stmttrns,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the BANKMSGSRSV1 type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the BANKMSGSRSV1 type", token),
+                            token,
+                            Self::TAGS,
+                            "BANKMSGSRSV1",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/BANKMSGSRSV1' token".to_string(),
-        ));
+            "/BANKMSGSRSV1",
+        ))
     }
 }
 
 impl<'a> Parseable<'a> for Stmttrnrs {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &["TRNUID", "STATUS", "STMTRS"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
         let mut s_trnuid = None;
         let mut s_status = None;
         let mut s_stmtrs = None;
 
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "TRNUID" => {
-                    if let Some(trnuid) = tokens.next() {
-                        s_trnuid = Some(trnuid.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected a token following the TRNUID token".to_string(),
-                        ));
-                    }
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("TRNUID") => {
+                    s_trnuid = Some(tokens.value("TRNUID")?);
                 }
-                "STATUS" => {
+                TokenKind::StartTag("STATUS") => {
                     s_status = Some(Status::parse(tokens)?);
                 }
-                "STMTRS" => s_stmtrs = Some(Stmtrs::parse(tokens)?),
-                "/STMTTRNRS" => {
+                TokenKind::StartTag("STMTRS") => s_stmtrs = Some(Stmtrs::parse(tokens)?),
+                TokenKind::EndTag("STMTTRNRS") => {
+                    // The statement is left out when the request failed
+                    let failed = s_status.as_ref().is_some_and(|x| !x.is_success());
+                    if s_stmtrs.is_none() && !failed {
+                        return Err(QFXParsingError::missing_value(
+                            "STMTRS section is required in STMTTRNRS".to_string(),
+                            "STMTRS",
+                        ));
+                    }
                     return Ok(Self {
                         trnuid: s_trnuid,
                         status: s_status,
-                        stmtrs: s_stmtrs.ok_or(QFXParsingError::MissingRequiredValue(
-                            "STMTRS section is required in STMTTRNRS".to_string(),
-                        ))?,
+                        stmtrs: s_stmtrs,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the STMTTRNRS type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the STMTTRNRS type", token),
+                            token,
+                            Self::TAGS,
+                            "STMTTRNRS",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/STMTTRNRS' token".to_string(),
-        ));
+            "/STMTTRNRS",
+        ))
     }
 }
 
 impl<'a> Parseable<'a> for Bankacctfrom {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &["BANKID", "ACCTID", "ACCTTYPE"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
+        Self::parse_aggregate(tokens, "BANKACCTFROM")
+    }
+}
+
+impl Bankacctfrom {
+    // BANKACCTFROM and BANKACCTTO have the same contents, so the end tag is passed in
+    pub(crate) fn parse_aggregate(
+        tokens: &mut TokenStream,
+        end_tag: &str,
+    ) -> Result<Self, QFXParsingError> {
         let mut s_acct_id = None;
         let mut s_acct_type = None;
         let mut s_bank_id = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "ACCTID" => {
-                    if let Some(curdef) = tokens.next() {
-                        s_acct_id = Some(curdef.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the ACCTID token".to_string(),
-                        ));
-                    }
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("ACCTID") => {
+                    s_acct_id = Some(tokens.value("ACCTID")?);
                 }
-                "ACCTTYPE" => {
-                    if let Some(acct_type) = tokens.next() {
-                        s_acct_type = Some(acct_type.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the ACCTTYPE token".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("ACCTTYPE") => {
+                    s_acct_type = Some(AccountType::from(tokens.value("ACCTTYPE")?.as_str()));
                 }
-                "BANKID" => {
-                    if let Some(bank_id) = tokens.next() {
-                        s_bank_id = Some(bank_id.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the ACCTTYPE token".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("BANKID") => {
+                    s_bank_id = Some(tokens.value("BANKID")?);
                 }
-                "/BANKACCTFROM" => {
+                TokenKind::EndTag(tag) if tag == end_tag => {
                     return Ok(Self {
-                        acct_id: s_acct_id.ok_or(QFXParsingError::MissingRequiredValue(
-                            "ACCTID is a required value in BANKACCTFROM".to_string(),
+                        acct_id: s_acct_id.ok_or(QFXParsingError::missing_value(
+                            format!("ACCTID is a required value in {}", end_tag),
+                            "ACCTID",
                         ))?,
-                        acct_type: s_acct_type.ok_or(QFXParsingError::MissingRequiredValue(
-                            "ACCTTYPE is a required value in BANKACCTFROM".to_string(),
+                        acct_type: s_acct_type.ok_or(QFXParsingError::missing_value(
+                            format!("ACCTTYPE is a required value in {}", end_tag),
+                            "ACCTTYPE",
                         ))?,
                         bank_id: s_bank_id,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the BANKACCTFROM type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the {} type", token, end_tag),
+                            token,
+                            <Self as Parseable>::TAGS,
+                            end_tag,
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
-            "Found unexpected EOF. Was still expecting the '/BANKACCTFROM' token".to_string(),
-        ));
+        Err(QFXParsingError::unexpected_eof(
+            format!(
+                "Found unexpected EOF. Was still expecting the '/{}' token",
+                end_tag
+            ),
+            &format!("/{}", end_tag),
+        ))
     }
 }
 
 impl<'a> Parseable<'a> for Stmtrs {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &[
+        "CURDEF",
+        "BANKACCTFROM",
+        "BANKTRANLIST",
+        "LEDGERBAL",
+        "AVAILBAL",
+        "BALLIST",
+        "MKTGINFO",
+    ];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
         let mut s_currency = None;
         let mut s_bankacctfrom = None;
         let mut s_banktranslist = None;
         let mut s_ledgerbal = None;
         let mut s_availbal = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "CURDEF" => {
-                    if let Some(curdef) = tokens.next() {
-                        s_currency = Some(curdef.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the CURDEF token".to_string(),
-                        ));
-                    }
+        let mut s_ballist = vec![];
+        let mut s_mktginfo = None;
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("CURDEF") => {
+                    s_currency = Some(tokens.value("CURDEF")?);
                 }
-                "BANKACCTFROM" => {
+                TokenKind::StartTag("BANKACCTFROM") => {
                     s_bankacctfrom = Some(Bankacctfrom::parse(tokens)?);
                 }
-                "BANKTRANLIST" => {
+                TokenKind::StartTag("BANKTRANLIST") => {
                     s_banktranslist = Some(BankTranList::parse(tokens)?);
                 }
-                "LEDGERBAL" => {
+                TokenKind::StartTag("LEDGERBAL") => {
                     s_ledgerbal = Some(LedgerBal::parse(tokens)?);
                 }
-                "AVAILBAL" => {
+                TokenKind::StartTag("AVAILBAL") => {
                     s_availbal = Some(AvailableBalance::parse(tokens)?);
                 }
-                "/STMTRS" => {
+                TokenKind::StartTag("BALLIST") => {
+                    s_ballist = parse_ballist(tokens)?;
+                }
+                TokenKind::StartTag("MKTGINFO") => {
+                    s_mktginfo = Some(tokens.value("MKTGINFO")?);
+                }
+                TokenKind::EndTag("STMTRS") => {
                     return Ok(Self {
                         currency: s_currency,
                         availbal: s_availbal,
-                        banktranslist: s_banktranslist.ok_or(
-                            QFXParsingError::MissingRequiredValue(
-                                "BANKTRANSLIST is a required value in STMTRS".to_string(),
-                            ),
-                        )?,
-                        bankacctfrom: s_bankacctfrom.ok_or(
-                            QFXParsingError::MissingRequiredValue(
-                                "BANKACCTFROM is a required value in STMTRS".to_string(),
-                            ),
-                        )?,
+                        banktranslist: s_banktranslist.ok_or(QFXParsingError::missing_value(
+                            "BANKTRANSLIST is a required value in STMTRS".to_string(),
+                            "BANKTRANLIST",
+                        ))?,
+                        bankacctfrom: s_bankacctfrom.ok_or(QFXParsingError::missing_value(
+                            "BANKACCTFROM is a required value in STMTRS".to_string(),
+                            "BANKACCTFROM",
+                        ))?,
                         ledgerbal: s_ledgerbal,
+                        ballist: s_ballist,
+                        mktginfo: s_mktginfo,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the STMTRS type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the STMTRS type", token),
+                            token,
+                            Self::TAGS,
+                            "STMTRS",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/STMTRS'".to_string(),
-        ));
+            "/STMTRS",
+        ))
     }
 }
 
@@ -247,7 +299,7 @@ mod test_bankacctfrom {
         assert!(result.is_ok());
         let bankacctfrom = result.unwrap();
         assert_eq!(bankacctfrom.acct_id, "1234567890");
-        assert_eq!(bankacctfrom.acct_type, "CHECKING");
+        assert_eq!(bankacctfrom.acct_type, AccountType::Checking);
         assert!(bankacctfrom.bank_id.is_none());
     }
 
@@ -264,7 +316,24 @@ mod test_bankacctfrom {
         assert!(result.is_ok());
         let bankacctfrom = result.unwrap();
         assert_eq!(bankacctfrom.acct_id, "1234567890");
-        assert_eq!(bankacctfrom.acct_type, "SAVINGS");
+        assert_eq!(bankacctfrom.acct_type, AccountType::Savings);
+        assert_eq!(bankacctfrom.bank_id, Some("123456789".to_string()));
+    }
+
+    #[test]
+    fn test_bankacctfrom_valid_with_leaf_end_tags() {
+        let input = "\
+            <BANKID>123456789</BANKID>\
+            <ACCTID>1234567890</ACCTID>\
+            <ACCTTYPE>SAVINGS\
+            </BANKACCTFROM>";
+        let mut tokens = tokenize(input);
+
+        let result = Bankacctfrom::parse(&mut tokens);
+        assert!(result.is_ok());
+        let bankacctfrom = result.unwrap();
+        assert_eq!(bankacctfrom.acct_id, "1234567890");
+        assert_eq!(bankacctfrom.acct_type, AccountType::Savings);
         assert_eq!(bankacctfrom.bank_id, Some("123456789".to_string()));
     }
 
@@ -279,7 +348,7 @@ mod test_bankacctfrom {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("ACCTID is a required value")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("ACCTID is a required value")
         ));
     }
 
@@ -294,7 +363,7 @@ mod test_bankacctfrom {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("ACCTTYPE is a required value")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("ACCTTYPE is a required value")
         ));
     }
 
@@ -311,7 +380,7 @@ mod test_bankacctfrom {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedToken(msg)) if msg.contains("UNKNOWNTAG") && msg.contains("BANKACCTFROM")
+            Err(QFXParsingError::UnexpectedToken { message: msg, .. }) if msg.contains("UNKNOWNTAG") && msg.contains("BANKACCTFROM")
         ));
     }
 
@@ -324,7 +393,7 @@ mod test_bankacctfrom {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Expected token following the ACCTID token")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Expected token following the ACCTID token")
         ));
     }
 
@@ -339,7 +408,7 @@ mod test_bankacctfrom {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Expected token following the ACCTTYPE token")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Expected token following the ACCTTYPE token")
         ));
     }
 
@@ -355,7 +424,7 @@ mod test_bankacctfrom {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Expected token following the ACCTTYPE token")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Expected token following the BANKID token")
         ));
     }
 
@@ -371,7 +440,7 @@ mod test_bankacctfrom {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Was still expecting the '/BANKACCTFROM' token")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Was still expecting the '/BANKACCTFROM' token")
         ));
     }
 }
@@ -402,8 +471,14 @@ mod test_stmttrnrs {
         let stmttrnrs = result.unwrap();
         assert!(stmttrnrs.trnuid.is_none());
         assert!(stmttrnrs.status.is_none());
-        assert_eq!(stmttrnrs.stmtrs.bankacctfrom.acct_id, "1234567890");
-        assert_eq!(stmttrnrs.stmtrs.bankacctfrom.acct_type, "CHECKING");
+        assert_eq!(
+            stmttrnrs.stmtrs.as_ref().unwrap().bankacctfrom.acct_id,
+            "1234567890"
+        );
+        assert_eq!(
+            stmttrnrs.stmtrs.as_ref().unwrap().bankacctfrom.acct_type,
+            AccountType::Checking
+        );
     }
 
     #[test]
@@ -428,8 +503,14 @@ mod test_stmttrnrs {
         let stmttrnrs = result.unwrap();
         assert_eq!(stmttrnrs.trnuid, Some("12345-67890".to_string()));
         assert!(stmttrnrs.status.is_none());
-        assert_eq!(stmttrnrs.stmtrs.bankacctfrom.acct_id, "1234567890");
-        assert_eq!(stmttrnrs.stmtrs.bankacctfrom.acct_type, "SAVINGS");
+        assert_eq!(
+            stmttrnrs.stmtrs.as_ref().unwrap().bankacctfrom.acct_id,
+            "1234567890"
+        );
+        assert_eq!(
+            stmttrnrs.stmtrs.as_ref().unwrap().bankacctfrom.acct_type,
+            AccountType::Savings
+        );
     }
 
     #[test]
@@ -457,8 +538,50 @@ mod test_stmttrnrs {
         let stmttrnrs = result.unwrap();
         assert!(stmttrnrs.trnuid.is_none());
         assert!(stmttrnrs.status.is_some());
-        assert_eq!(stmttrnrs.stmtrs.bankacctfrom.acct_id, "1234567890");
-        assert_eq!(stmttrnrs.stmtrs.bankacctfrom.acct_type, "CHECKING");
+        assert_eq!(
+            stmttrnrs.stmtrs.as_ref().unwrap().bankacctfrom.acct_id,
+            "1234567890"
+        );
+        assert_eq!(
+            stmttrnrs.stmtrs.as_ref().unwrap().bankacctfrom.acct_type,
+            AccountType::Checking
+        );
+    }
+
+    #[test]
+    fn test_stmttrnrs_error_status_without_stmtrs() {
+        let input = "\
+            <TRNUID>1001\
+            <STATUS>\
+                <CODE>2003\
+                <SEVERITY>ERROR\
+                <MESSAGE>Account not found\
+            </STATUS>\
+            </STMTTRNRS>";
+        let mut tokens = tokenize(input);
+
+        let stmttrnrs = Stmttrnrs::parse(&mut tokens).unwrap();
+        assert!(stmttrnrs.stmtrs.is_none());
+        let status = stmttrnrs.status.unwrap();
+        assert_eq!(status.code, crate::StatusCode::AccountNotFound);
+        assert!(!status.is_success());
+    }
+
+    #[test]
+    fn test_stmttrnrs_success_status_without_stmtrs() {
+        let input = "\
+            <STATUS>\
+                <CODE>0\
+                <SEVERITY>INFO\
+            </STATUS>\
+            </STMTTRNRS>";
+        let mut tokens = tokenize(input);
+
+        let result = Stmttrnrs::parse(&mut tokens);
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::MissingRequiredValue { field, .. }) if field == "STMTRS"
+        ));
     }
 
     #[test]
@@ -472,7 +595,7 @@ mod test_stmttrnrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("STMTRS section is required")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("STMTRS section is required")
         ));
     }
 
@@ -497,7 +620,7 @@ mod test_stmttrnrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedToken(msg)) if msg.contains("UNKNOWNTAG") && msg.contains("STMTTRNRS")
+            Err(QFXParsingError::UnexpectedToken { message: msg, .. }) if msg.contains("UNKNOWNTAG") && msg.contains("STMTTRNRS")
         ));
     }
 
@@ -510,7 +633,7 @@ mod test_stmttrnrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Expected a token following the TRNUID token")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Expected token following the TRNUID token")
         ));
     }
 
@@ -534,7 +657,7 @@ mod test_stmttrnrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Was still expecting the '/STMTTRNRS' token")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Was still expecting the '/STMTTRNRS' token")
         ));
     }
 }
@@ -542,6 +665,8 @@ mod test_stmttrnrs {
 #[cfg(test)]
 mod test_stmtrs {
     use super::*;
+    use crate::Amount;
+    use crate::BalList;
     use crate::tokenize;
 
     #[test]
@@ -563,7 +688,7 @@ mod test_stmtrs {
         let stmtrs = result.unwrap();
         assert!(stmtrs.currency.is_none());
         assert_eq!(stmtrs.bankacctfrom.acct_id, "1234567890");
-        assert_eq!(stmtrs.bankacctfrom.acct_type, "CHECKING");
+        assert_eq!(stmtrs.bankacctfrom.acct_type, AccountType::Checking);
         assert!(stmtrs.ledgerbal.is_none());
         assert!(stmtrs.availbal.is_none());
     }
@@ -588,7 +713,7 @@ mod test_stmtrs {
         let stmtrs = result.unwrap();
         assert_eq!(stmtrs.currency, Some("USD".to_string()));
         assert_eq!(stmtrs.bankacctfrom.acct_id, "1234567890");
-        assert_eq!(stmtrs.bankacctfrom.acct_type, "SAVINGS");
+        assert_eq!(stmtrs.bankacctfrom.acct_type, AccountType::Savings);
     }
 
     #[test]
@@ -670,12 +795,75 @@ mod test_stmtrs {
         let stmtrs = result.unwrap();
         assert_eq!(stmtrs.currency, Some("CAD".to_string()));
         assert_eq!(stmtrs.bankacctfrom.acct_id, "1234567890");
-        assert_eq!(stmtrs.bankacctfrom.acct_type, "SAVINGS");
+        assert_eq!(stmtrs.bankacctfrom.acct_type, AccountType::Savings);
         assert_eq!(stmtrs.bankacctfrom.bank_id, Some("123456789".to_string()));
         assert!(stmtrs.ledgerbal.is_some());
         assert!(stmtrs.availbal.is_some());
     }
 
+    #[test]
+    fn test_stmtrs_valid_with_ballist() {
+        let input = "\
+            <CURDEF>USD\
+            <BANKACCTFROM>\
+                <ACCTID>1234567890\
+                <ACCTTYPE>SAVINGS\
+            </BANKACCTFROM>\
+            <BANKTRANLIST>\
+                <DTSTART>20250715080000\
+                <DTEND>20250716090000\
+            </BANKTRANLIST>\
+            <BALLIST>\
+                <BAL>\
+                    <NAME>INTYTD\
+                    <DESC>Interest paid year to date\
+                    <BALTYPE>DOLLAR\
+                    <VALUE>12.34\
+                    <CURRENCY><CURRATE>1.0<CURSYM>USD</CURRENCY>\
+                </BAL>\
+            </BALLIST>\
+            </STMTRS>";
+        let mut tokens = tokenize(input);
+
+        let stmtrs = Stmtrs::parse(&mut tokens).unwrap();
+        assert_eq!(stmtrs.ballist.len(), 1);
+        let interest = stmtrs.ballist.interest_ytd().unwrap();
+        assert_eq!(interest.desc, "Interest paid year to date");
+        assert_eq!(interest.value, Amount::new(1234, 2));
+        assert_eq!(interest.currency.as_ref().unwrap().symbol, "USD");
+        assert!(interest.dt_as_of.is_none());
+        assert!(
+            stmtrs
+                .ballist
+                .balance("interest paid year-to-date")
+                .is_some()
+        );
+        assert!(stmtrs.ballist.minimum_payment_due().is_none());
+        assert!(stmtrs.mktginfo.is_none());
+    }
+
+    #[test]
+    fn test_stmtrs_valid_with_mktginfo() {
+        let input = "\
+            <BANKACCTFROM>\
+                <ACCTID>1234567890\
+                <ACCTTYPE>CHECKING\
+            </BANKACCTFROM>\
+            <BANKTRANLIST>\
+                <DTSTART>20250715080000\
+                <DTEND>20250716090000\
+            </BANKTRANLIST>\
+            <MKTGINFO>Ask us about our new savings rates</MKTGINFO>\
+            </STMTRS>";
+        let mut tokens = tokenize(input);
+
+        let stmtrs = Stmtrs::parse(&mut tokens).unwrap();
+        assert_eq!(
+            stmtrs.mktginfo,
+            Some("Ask us about our new savings rates".to_string())
+        );
+    }
+
     #[test]
     fn test_stmtrs_missing_bankacctfrom() {
         let input = "\
@@ -690,7 +878,7 @@ mod test_stmtrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("BANKACCTFROM is a required value")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("BANKACCTFROM is a required value")
         ));
     }
 
@@ -708,7 +896,7 @@ mod test_stmtrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("BANKTRANSLIST is a required value")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("BANKTRANSLIST is a required value")
         ));
     }
 
@@ -731,7 +919,7 @@ mod test_stmtrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedToken(msg)) if msg.contains("UNKNOWNTAG") && msg.contains("STMTRS")
+            Err(QFXParsingError::UnexpectedToken { message: msg, .. }) if msg.contains("UNKNOWNTAG") && msg.contains("STMTRS")
         ));
     }
 
@@ -744,7 +932,7 @@ mod test_stmtrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Expected token following the CURDEF token")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Expected token following the CURDEF token")
         ));
     }
 
@@ -766,7 +954,7 @@ mod test_stmtrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Was still expecting the '/STMTRS'")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Was still expecting the '/STMTRS'")
         ));
     }
 }
@@ -797,15 +985,91 @@ mod test_bankmsgsrsv1 {
         let result = BankMsgSrsV1::parse(&mut tokens);
         assert!(result.is_ok());
         let bankmsgsrsv1 = result.unwrap();
-        assert!(bankmsgsrsv1.stmttrns.trnuid.is_none());
-        assert!(bankmsgsrsv1.stmttrns.status.is_none());
+        assert_eq!(bankmsgsrsv1.stmttrns.len(), 1);
+        assert!(bankmsgsrsv1.stmttrns[0].trnuid.is_none());
+        assert!(bankmsgsrsv1.stmttrns[0].status.is_none());
         assert_eq!(
-            bankmsgsrsv1.stmttrns.stmtrs.bankacctfrom.acct_id,
+            bankmsgsrsv1.stmttrns[0]
+                .stmtrs
+                .as_ref()
+                .unwrap()
+                .bankacctfrom
+                .acct_id,
             "1234567890"
         );
         assert_eq!(
-            bankmsgsrsv1.stmttrns.stmtrs.bankacctfrom.acct_type,
-            "CHECKING"
+            bankmsgsrsv1.stmttrns[0]
+                .stmtrs
+                .as_ref()
+                .unwrap()
+                .bankacctfrom
+                .acct_type,
+            AccountType::Checking
+        );
+    }
+
+    #[test]
+    fn test_bankmsgsrsv1_multiple_stmttrnrs() {
+        let input = "\
+            <STMTTRNRS>\
+                <TRNUID>1\
+                <STMTRS>\
+                    <BANKACCTFROM>\
+                        <ACCTID>1111\
+                        <ACCTTYPE>CHECKING\
+                    </BANKACCTFROM>\
+                    <BANKTRANLIST>\
+                        <DTSTART>20250715080000\
+                        <DTEND>20250716090000\
+                    </BANKTRANLIST>\
+                </STMTRS>\
+            </STMTTRNRS>\
+            <STMTTRNRS>\
+                <TRNUID>2\
+                <STMTRS>\
+                    <BANKACCTFROM>\
+                        <ACCTID>2222\
+                        <ACCTTYPE>SAVINGS\
+                    </BANKACCTFROM>\
+                    <BANKTRANLIST>\
+                        <DTSTART>20250715080000\
+                        <DTEND>20250716090000\
+                    </BANKTRANLIST>\
+                </STMTRS>\
+            </STMTTRNRS>\
+            </BANKMSGSRSV1>";
+        let mut tokens = tokenize(input);
+
+        let bankmsgsrsv1 = BankMsgSrsV1::parse(&mut tokens).unwrap();
+        assert_eq!(bankmsgsrsv1.stmttrns.len(), 2);
+        assert_eq!(bankmsgsrsv1.stmttrns[0].trnuid, Some("1".to_string()));
+        assert_eq!(
+            bankmsgsrsv1.stmttrns[0]
+                .stmtrs
+                .as_ref()
+                .unwrap()
+                .bankacctfrom
+                .acct_id,
+            "1111"
+        );
+        assert_eq!(bankmsgsrsv1.stmttrns[1].trnuid, Some("2".to_string()));
+        assert_eq!(
+            bankmsgsrsv1.stmttrns[1]
+                .stmtrs
+                .as_ref()
+                .unwrap()
+                .bankacctfrom
+                .acct_id,
+            "2222"
+        );
+        assert_eq!(
+            bankmsgsrsv1.stmttrns[1]
+                .stmtrs
+                .as_ref()
+                .unwrap()
+                .bankacctfrom
+                .acct_type,
+            AccountType::Savings
         );
     }
 
@@ -818,7 +1082,7 @@ mod test_bankmsgsrsv1 {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("STMTTRNRS is a required value")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("STMTTRNRS is a required value")
         ));
     }
 
@@ -845,7 +1109,7 @@ mod test_bankmsgsrsv1 {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedToken(msg)) if msg.contains("UNKNOWNTAG") && msg.contains("BANKMSGSRSV1")
+            Err(QFXParsingError::UnexpectedToken { message: msg, .. }) if msg.contains("UNKNOWNTAG") && msg.contains("BANKMSGSRSV1")
         ));
     }
 
@@ -870,7 +1134,7 @@ mod test_bankmsgsrsv1 {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Was still expecting the '/BANKMSGSRSV1' token")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Was still expecting the '/BANKMSGSRSV1' token")
         ));
     }
 }
@@ -1,20 +1,25 @@
+use crate::Amount;
 use crate::AvailableBalance;
+use crate::Bal;
 use crate::BankTranList;
 use crate::LedgerBal;
 use crate::Parseable;
 use crate::QFXParsingError;
 use crate::Status;
+use crate::balance::parse_ballist;
+use crate::lexer::TokenKind;
+use crate::lexer::TokenStream;
 
 #[derive(Clone)]
 pub struct CCMsgSrsV1 {
-    pub ccstmttrns: Ccstmttrnrs, // TODO: This should be a vector, there can be more than 1 of these, but there should be at least 1
+    pub ccstmttrns: Vec<Ccstmttrnrs>, // There has to be at least 1 of these
 }
 
 #[derive(Clone)]
 pub struct Ccstmttrnrs {
     pub trnuid: Option<String>,
     pub status: Option<Status>,
-    pub ccstmtrs: Ccstmtrs, // TODO: Should not be a pub(crate) visibility
+    pub ccstmtrs: Option<Ccstmtrs>, // Missing when the STATUS is an error
 }
 
 #[derive(Clone)]
@@ -24,181 +29,324 @@ pub struct Ccstmtrs {
     pub banktranslist: BankTranList,
     pub ledgerbal: Option<LedgerBal>,
     pub availbal: Option<AvailableBalance>,
+    pub ballist: Vec<Bal>, // Empty when there is no BALLIST. See BalList for lookups
+    pub rewardinfo: Option<RewardInfo>,
+    /// Marketing text from the card issuer
+    pub mktginfo: Option<String>,
 }
 
-#[derive(Clone)]
+/// The REWARDINFO aggregate of a credit card statement. Eg. the points of a rewards card.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RewardInfo {
+    /// Name of the rewards program
+    pub name: String,
+    /// Current balance of the rewards
+    pub reward_bal: Amount,
+    /// Rewards earned in the statement period
+    pub reward_earned: Option<Amount>,
+}
+
+/// A credit card account. Used for CCACCTFROM and for the CCACCTTO of a transfer.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Ccacctfrom {
     pub acct_id: String,
 }
 
 impl<'a> Parseable<'a> for CCMsgSrsV1 {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
-        let mut s_ccstmttrns = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "CCSTMTTRNRS" => {
-                    s_ccstmttrns = Some(Ccstmttrnrs::parse(tokens)?);
+    const TAGS: &'static [&'static str] = &["CCSTMTTRNRS"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
+        let mut s_ccstmttrns: Vec<Ccstmttrnrs> = vec![];
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("CCSTMTTRNRS") => {
+                    s_ccstmttrns.push(Ccstmttrnrs::parse(tokens)?);
                 }
-                "/CREDITCARDMSGSRSV1" => {
+                TokenKind::EndTag("CREDITCARDMSGSRSV1") => {
+                    if s_ccstmttrns.is_empty() {
+                        return Err(QFXParsingError::missing_value(
+                            "CCSTMTTRNRS is a required value in CREDITCARDMSGSRSV1".to_string(),
+                            "CCSTMTTRNRS",
+                        ));
+                    }
                     return Ok(Self {
-                        ccstmttrns: s_ccstmttrns.ok_or(QFXParsingError::MissingRequiredValue(
-                            "CCSTMTTRNRS is a requied value in CREDITCARDMSGSRSV1".to_string(),
-                        ))?,
+                        ccstmttrns: s_ccstmttrns,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the CREDITCARDMSGSRSV1 type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!(
+                                "Found unexpected token {} in the CREDITCARDMSGSRSV1 type",
+                                token
+                            ),
+                            token,
+                            Self::TAGS,
+                            "CREDITCARDMSGSRSV1",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/CREDITCARDMSGSRSV1' token".to_string(),
-        ));
+            "/CREDITCARDMSGSRSV1",
+        ))
     }
 }
 
 impl<'a> Parseable<'a> for Ccstmttrnrs {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &["TRNUID", "STATUS", "CCSTMTRS"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
         let mut s_trnuid = None;
         let mut s_status = None;
         let mut s_ccstmtrs = None;
 
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "TRNUID" => {
-                    if let Some(trnuid) = tokens.next() {
-                        s_trnuid = Some(trnuid.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the TRNUID token".to_string(),
-                        ));
-                    }
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("TRNUID") => {
+                    s_trnuid = Some(tokens.value("TRNUID")?);
                 }
-                "STATUS" => {
+                TokenKind::StartTag("STATUS") => {
                     s_status = Some(Status::parse(tokens)?);
                 }
-                "CCSTMTRS" => s_ccstmtrs = Some(Ccstmtrs::parse(tokens)?),
-                "/CCSTMTTRNRS" => {
+                TokenKind::StartTag("CCSTMTRS") => s_ccstmtrs = Some(Ccstmtrs::parse(tokens)?),
+                TokenKind::EndTag("CCSTMTTRNRS") => {
+                    // The statement is left out when the request failed
+                    let failed = s_status.as_ref().is_some_and(|x| !x.is_success());
+                    if s_ccstmtrs.is_none() && !failed {
+                        return Err(QFXParsingError::missing_value(
+                            "CCSTMTRS section is required in CCSTMTTRNRS".to_string(),
+                            "CCSTMTRS",
+                        ));
+                    }
                     return Ok(Self {
                         trnuid: s_trnuid,
                         status: s_status,
-                        ccstmtrs: s_ccstmtrs.ok_or(QFXParsingError::MissingRequiredValue(
-                            "CCSTMTRS section is required in CCSTMTTRNRS".to_string(),
-                        ))?,
+                        ccstmtrs: s_ccstmtrs,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the CCSTMTTRNRS type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the CCSTMTTRNRS type", token),
+                            token,
+                            Self::TAGS,
+                            "CCSTMTTRNRS",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/CCSTMTTRNRS' token".to_string(),
-        ));
+            "/CCSTMTTRNRS",
+        ))
     }
 }
 
 impl<'a> Parseable<'a> for Ccstmtrs {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &[
+        "CURDEF",
+        "CCACCTFROM",
+        "BANKTRANLIST",
+        "LEDGERBAL",
+        "AVAILBAL",
+        "BALLIST",
+        "REWARDINFO",
+        "MKTGINFO",
+    ];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
         let mut s_currency = None;
         let mut s_ccacctfrom = None;
         let mut s_banktranslist = None;
         let mut s_ledgerbal = None;
         let mut s_availbal = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "CURDEF" => {
-                    if let Some(curdef) = tokens.next() {
-                        s_currency = Some(curdef.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the CURDEF token".to_string(),
-                        ));
-                    }
+        let mut s_ballist = vec![];
+        let mut s_rewardinfo = None;
+        let mut s_mktginfo = None;
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("CURDEF") => {
+                    s_currency = Some(tokens.value("CURDEF")?);
                 }
-                "CCACCTFROM" => {
+                TokenKind::StartTag("CCACCTFROM") => {
                     s_ccacctfrom = Some(Ccacctfrom::parse(tokens)?);
                 }
-                "BANKTRANLIST" => {
+                TokenKind::StartTag("BANKTRANLIST") => {
                     s_banktranslist = Some(BankTranList::parse(tokens)?);
                 }
-                "LEDGERBAL" => {
+                TokenKind::StartTag("LEDGERBAL") => {
                     s_ledgerbal = Some(LedgerBal::parse(tokens)?);
                 }
-                "AVAILBAL" => {
+                TokenKind::StartTag("AVAILBAL") => {
                     s_availbal = Some(AvailableBalance::parse(tokens)?);
                 }
-                "/CCSTMTRS" => {
+                TokenKind::StartTag("BALLIST") => {
+                    s_ballist = parse_ballist(tokens)?;
+                }
+                TokenKind::StartTag("REWARDINFO") => {
+                    s_rewardinfo = Some(RewardInfo::parse(tokens)?);
+                }
+                TokenKind::StartTag("MKTGINFO") => {
+                    s_mktginfo = Some(tokens.value("MKTGINFO")?);
+                }
+                TokenKind::EndTag("CCSTMTRS") => {
                     return Ok(Self {
                         currency: s_currency,
                         availbal: s_availbal,
-                        banktranslist: s_banktranslist.ok_or(
-                            QFXParsingError::MissingRequiredValue(
-                                "BANKTRANSLIST is a required value in CCSTMTRS".to_string(),
-                            ),
-                        )?,
-                        ccacctfrom: s_ccacctfrom.ok_or(QFXParsingError::MissingRequiredValue(
+                        banktranslist: s_banktranslist.ok_or(QFXParsingError::missing_value(
+                            "BANKTRANSLIST is a required value in CCSTMTRS".to_string(),
+                            "BANKTRANLIST",
+                        ))?,
+                        ccacctfrom: s_ccacctfrom.ok_or(QFXParsingError::missing_value(
                             "CCACCTFROM is a required value in CCSTMTRS".to_string(),
+                            "CCACCTFROM",
                         ))?,
                         ledgerbal: s_ledgerbal,
+                        ballist: s_ballist,
+                        rewardinfo: s_rewardinfo,
+                        mktginfo: s_mktginfo,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the CCSTMTRS type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the CCSTMTRS type", token),
+                            token,
+                            Self::TAGS,
+                            "CCSTMTRS",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/CCSTMTRS'".to_string(),
-        ));
+            "/CCSTMTRS",
+        ))
     }
 }
 
 impl<'a> Parseable<'a> for Ccacctfrom {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &["ACCTID"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
+        Self::parse_aggregate(tokens, "CCACCTFROM")
+    }
+}
+
+impl<'a> Parseable<'a> for RewardInfo {
+    const TAGS: &'static [&'static str] = &["NAME", "REWARDBAL", "REWARDEARNED"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
+        let mut s_name = None;
+        let mut s_reward_bal = None;
+        let mut s_reward_earned = None;
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("NAME") => {
+                    s_name = Some(tokens.value("NAME")?);
+                }
+                TokenKind::StartTag("REWARDBAL") => {
+                    s_reward_bal = Some(reward_amount(tokens.value("REWARDBAL")?)?);
+                }
+                TokenKind::StartTag("REWARDEARNED") => {
+                    s_reward_earned = Some(reward_amount(tokens.value("REWARDEARNED")?)?);
+                }
+                TokenKind::EndTag("REWARDINFO") => {
+                    return Ok(Self {
+                        name: s_name.ok_or(QFXParsingError::missing_value(
+                            "NAME is a required value in REWARDINFO".to_string(),
+                            "NAME",
+                        ))?,
+                        reward_bal: s_reward_bal.ok_or(QFXParsingError::missing_value(
+                            "REWARDBAL is a required value in REWARDINFO".to_string(),
+                            "REWARDBAL",
+                        ))?,
+                        reward_earned: s_reward_earned,
+                    });
+                }
+                _ => {
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the REWARDINFO type", token),
+                            token,
+                            Self::TAGS,
+                            "REWARDINFO",
+                        ),
+                    )?;
+                }
+            }
+        }
+        Err(QFXParsingError::unexpected_eof(
+            "Found unexpected EOF. Was still expecting the '/REWARDINFO' token".to_string(),
+            "/REWARDINFO",
+        ))
+    }
+}
+
+// Rewards are points or miles rather than money but are written the same way as an amount
+fn reward_amount(value: String) -> Result<Amount, QFXParsingError> {
+    value.parse::<Amount>().map_err(|e| {
+        QFXParsingError::invalid_amount(
+            format!("Invalid reward amount {} with {}", value, e),
+            &value,
+        )
+    })
+}
+
+impl Ccacctfrom {
+    // CCACCTFROM and CCACCTTO have the same contents, so the end tag is passed in
+    pub(crate) fn parse_aggregate(
+        tokens: &mut TokenStream,
+        end_tag: &str,
+    ) -> Result<Self, QFXParsingError> {
         let mut s_acct_id = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "ACCTID" => {
-                    if let Some(curdef) = tokens.next() {
-                        s_acct_id = Some(curdef.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the ACCTID token".to_string(),
-                        ));
-                    }
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("ACCTID") => {
+                    s_acct_id = Some(tokens.value("ACCTID")?);
                 }
-                "/CCACCTFROM" => {
+                TokenKind::EndTag(tag) if tag == end_tag => {
                     return Ok(Self {
-                        acct_id: s_acct_id.ok_or(QFXParsingError::MissingRequiredValue(
-                            "ACCTID is a required value in CCSTMTRS".to_string(),
+                        acct_id: s_acct_id.ok_or(QFXParsingError::missing_value(
+                            format!("ACCTID is a required value in {}", end_tag),
+                            "ACCTID",
                         ))?,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the CCACCTFROM type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the {} type", token, end_tag),
+                            token,
+                            <Self as Parseable>::TAGS,
+                            end_tag,
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
-            "Found unexpected EOF. Was still expecting the '/CCACCTFROM' token".to_string(),
-        ));
+        Err(QFXParsingError::unexpected_eof(
+            format!(
+                "Found unexpected EOF. Was still expecting the '/{}' token",
+                end_tag
+            ),
+            &format!("/{}", end_tag),
+        ))
     }
 }
 
@@ -220,6 +368,16 @@ mod test_ccacctfrom {
         assert_eq!(ccacctfrom.acct_id, "1234567890");
     }
 
+    #[test]
+    fn test_ccacctfrom_valid_with_leaf_end_tag() {
+        let input = "<ACCTID>1234567890</ACCTID></CCACCTFROM>";
+        let mut tokens = tokenize(input);
+
+        let result = Ccacctfrom::parse(&mut tokens);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().acct_id, "1234567890");
+    }
+
     #[test]
     fn test_ccacctfrom_missing_acctid() {
         let input = "</CCACCTFROM>";
@@ -228,7 +386,7 @@ mod test_ccacctfrom {
         let result = Ccacctfrom::parse(&mut tokens);
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("ACCTID is a required value")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("ACCTID is a required value")
         ));
     }
 
@@ -241,7 +399,7 @@ mod test_ccacctfrom {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedToken(msg)) if msg.contains("UNKNOWNTAG") && msg.contains("CCACCTFROM")
+            Err(QFXParsingError::UnexpectedToken { message: msg, .. }) if msg.contains("UNKNOWNTAG") && msg.contains("CCACCTFROM")
         ));
     }
 }
@@ -271,7 +429,10 @@ mod test_ccstmttrnrs {
         let ccstmttrnrs = result.unwrap();
         assert!(ccstmttrnrs.trnuid.is_none());
         assert!(ccstmttrnrs.status.is_none());
-        assert_eq!(ccstmttrnrs.ccstmtrs.ccacctfrom.acct_id, "1234567890");
+        assert_eq!(
+            ccstmttrnrs.ccstmtrs.as_ref().unwrap().ccacctfrom.acct_id,
+            "1234567890"
+        );
     }
 
     #[test]
@@ -295,7 +456,10 @@ mod test_ccstmttrnrs {
         let ccstmttrnrs = result.unwrap();
         assert_eq!(ccstmttrnrs.trnuid, Some("12345-67890".to_string()));
         assert!(ccstmttrnrs.status.is_none());
-        assert_eq!(ccstmttrnrs.ccstmtrs.ccacctfrom.acct_id, "1234567890");
+        assert_eq!(
+            ccstmttrnrs.ccstmtrs.as_ref().unwrap().ccacctfrom.acct_id,
+            "1234567890"
+        );
     }
 
     #[test]
@@ -322,7 +486,25 @@ mod test_ccstmttrnrs {
         let ccstmttrnrs = result.unwrap();
         assert!(ccstmttrnrs.trnuid.is_none());
         assert!(ccstmttrnrs.status.is_some());
-        assert_eq!(ccstmttrnrs.ccstmtrs.ccacctfrom.acct_id, "1234567890");
+        assert_eq!(
+            ccstmttrnrs.ccstmtrs.as_ref().unwrap().ccacctfrom.acct_id,
+            "1234567890"
+        );
+    }
+
+    #[test]
+    fn test_ccstmttrnrs_error_status_without_ccstmtrs() {
+        let input = "\
+            <STATUS>\
+                <CODE>2000\
+                <SEVERITY>ERROR\
+            </STATUS>\
+            </CCSTMTTRNRS>";
+        let mut tokens = tokenize(input);
+
+        let ccstmttrnrs = Ccstmttrnrs::parse(&mut tokens).unwrap();
+        assert!(ccstmttrnrs.ccstmtrs.is_none());
+        assert!(!ccstmttrnrs.status.unwrap().is_success());
     }
 
     #[test]
@@ -336,7 +518,7 @@ mod test_ccstmttrnrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("CCSTMTRS section is required")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("CCSTMTRS section is required")
         ));
     }
 
@@ -360,7 +542,7 @@ mod test_ccstmttrnrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedToken(msg)) if msg.contains("UNKNOWNTAG") && msg.contains("CCSTMTTRNRS")
+            Err(QFXParsingError::UnexpectedToken { message: msg, .. }) if msg.contains("UNKNOWNTAG") && msg.contains("CCSTMTTRNRS")
         ));
     }
 
@@ -373,7 +555,7 @@ mod test_ccstmttrnrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Expected token following the TRNUID token")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Expected token following the TRNUID token")
         ));
     }
 
@@ -396,7 +578,7 @@ mod test_ccstmttrnrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Was still expecting the '/CCSTMTTRNRS' token")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Was still expecting the '/CCSTMTTRNRS' token")
         ));
     }
 }
@@ -404,6 +586,9 @@ mod test_ccstmttrnrs {
 #[cfg(test)]
 mod test_ccstmtrs {
     use super::*;
+    use crate::Amount;
+    use crate::BalList;
+    use crate::BalanceType;
     use crate::tokenize;
 
     #[test]
@@ -497,6 +682,125 @@ mod test_ccstmtrs {
         assert_eq!(ccstmtrs.ccacctfrom.acct_id, "1234567890");
     }
 
+    #[test]
+    fn test_ccstmtrs_valid_with_ballist() {
+        let input = "\
+            <CCACCTFROM>\
+                <ACCTID>1234567890\
+            </CCACCTFROM>\
+            <BANKTRANLIST>\
+                <DTSTART>20250715080000\
+                <DTEND>20250716090000\
+            </BANKTRANLIST>\
+            <BALLIST>\
+                <BAL>\
+                    <NAME>Minimum payment due\
+                    <DESC>Minimum payment due by 20250810\
+                    <BALTYPE>DOLLAR\
+                    <VALUE>35.00\
+                    <DTASOF>20250715\
+                </BAL>\
+                <BAL>\
+                    <NAME>CREDITLIMIT\
+                    <DESC>Credit limit\
+                    <BALTYPE>DOLLAR\
+                    <VALUE>5000.00\
+                </BAL>\
+                <BAL>\
+                    <NAME>APR\
+                    <DESC>Purchase APR\
+                    <BALTYPE>PERCENT\
+                    <VALUE>24.99\
+                </BAL>\
+            </BALLIST>\
+            </CCSTMTRS>";
+        let mut tokens = tokenize(input);
+
+        let ccstmtrs = Ccstmtrs::parse(&mut tokens).unwrap();
+        assert_eq!(ccstmtrs.ballist.len(), 3);
+        let minimum_payment = ccstmtrs.ballist.minimum_payment_due().unwrap();
+        assert_eq!(minimum_payment.value, Amount::new(3500, 2));
+        assert!(minimum_payment.dt_as_of.is_some());
+        assert_eq!(
+            ccstmtrs.ballist.credit_limit().unwrap().value,
+            Amount::new(500000, 2)
+        );
+        let apr = ccstmtrs.ballist.balance("purchase apr").unwrap();
+        assert_eq!(apr.bal_type, BalanceType::Percent);
+        assert_eq!(apr.value.to_string(), "24.99");
+        assert!(ccstmtrs.ballist.statement_balance().is_none());
+        assert!(ccstmtrs.ballist.interest_ytd().is_none());
+    }
+
+    #[test]
+    fn test_ccstmtrs_ballist_missing_value() {
+        let input = "\
+            <BALLIST>\
+                <BAL>\
+                    <NAME>CREDITLIMIT\
+                    <DESC>Credit limit\
+                    <BALTYPE>DOLLAR\
+                </BAL>\
+            </BALLIST>\
+            </CCSTMTRS>";
+        let mut tokens = tokenize(input);
+
+        let result = Ccstmtrs::parse(&mut tokens);
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::MissingRequiredValue { field, .. }) if field == "VALUE"
+        ));
+    }
+
+    #[test]
+    fn test_ccstmtrs_valid_with_rewardinfo_and_mktginfo() {
+        let input = "\
+            <CCACCTFROM>\
+                <ACCTID>1234567890\
+            </CCACCTFROM>\
+            <BANKTRANLIST>\
+                <DTSTART>20250715080000\
+                <DTEND>20250716090000\
+            </BANKTRANLIST>\
+            <REWARDINFO>\
+                <NAME>Travel Points\
+                <REWARDBAL>15230\
+                <REWARDEARNED>412.5\
+            </REWARDINFO>\
+            <MKTGINFO>Earn 3x points on dining this month\
+            </CCSTMTRS>";
+        let mut tokens = tokenize(input);
+
+        let ccstmtrs = Ccstmtrs::parse(&mut tokens).unwrap();
+        assert_eq!(
+            ccstmtrs.rewardinfo,
+            Some(RewardInfo {
+                name: "Travel Points".to_string(),
+                reward_bal: Amount::new(15230, 0),
+                reward_earned: Some(Amount::new(4125, 1)),
+            })
+        );
+        assert_eq!(
+            ccstmtrs.mktginfo,
+            Some("Earn 3x points on dining this month".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ccstmtrs_rewardinfo_invalid_and_missing_values() {
+        let mut tokens = tokenize("<NAME>Points<REWARDBAL>lots</REWARDINFO>");
+        assert!(matches!(
+            RewardInfo::parse(&mut tokens),
+            Err(QFXParsingError::InvalidTransactionAmount { value, .. }) if value == "lots"
+        ));
+
+        let mut tokens = tokenize("<NAME>Points<REWARDEARNED>10</REWARDINFO>");
+        assert!(matches!(
+            RewardInfo::parse(&mut tokens),
+            Err(QFXParsingError::MissingRequiredValue { field, .. }) if field == "REWARDBAL"
+        ));
+    }
+
     #[test]
     fn test_ccstmtrs_missing_ccacctfrom() {
         let input = "\
@@ -511,7 +815,7 @@ mod test_ccstmtrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("CCACCTFROM is a required value")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("CCACCTFROM is a required value")
         ));
     }
 
@@ -528,7 +832,7 @@ mod test_ccstmtrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("BANKTRANSLIST is a required value")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("BANKTRANSLIST is a required value")
         ));
     }
 
@@ -550,7 +854,7 @@ mod test_ccstmtrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedToken(msg)) if msg.contains("UNKNOWNTAG") && msg.contains("CCSTMTRS")
+            Err(QFXParsingError::UnexpectedToken { message: msg, .. }) if msg.contains("UNKNOWNTAG") && msg.contains("CCSTMTRS")
         ));
     }
 
@@ -563,7 +867,7 @@ mod test_ccstmtrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Expected token following the CURDEF token")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Expected token following the CURDEF token")
         ));
     }
 
@@ -584,7 +888,7 @@ mod test_ccstmtrs {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Was still expecting the '/CCSTMTRS'")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Was still expecting the '/CCSTMTRS'")
         ));
     }
 }
@@ -614,14 +918,74 @@ mod test_ccmsgsrsv1 {
         let result = CCMsgSrsV1::parse(&mut tokens);
         assert!(result.is_ok());
         let ccmsgsrsv1 = result.unwrap();
-        assert!(ccmsgsrsv1.ccstmttrns.trnuid.is_none());
-        assert!(ccmsgsrsv1.ccstmttrns.status.is_none());
+        assert_eq!(ccmsgsrsv1.ccstmttrns.len(), 1);
+        assert!(ccmsgsrsv1.ccstmttrns[0].trnuid.is_none());
+        assert!(ccmsgsrsv1.ccstmttrns[0].status.is_none());
         assert_eq!(
-            ccmsgsrsv1.ccstmttrns.ccstmtrs.ccacctfrom.acct_id,
+            ccmsgsrsv1.ccstmttrns[0]
+                .ccstmtrs
+                .as_ref()
+                .unwrap()
+                .ccacctfrom
+                .acct_id,
             "1234567890"
         );
     }
 
+    #[test]
+    fn test_ccmsgsrsv1_multiple_ccstmttrnrs() {
+        let input = "\
+            <CCSTMTTRNRS>\
+                <TRNUID>1\
+                <CCSTMTRS>\
+                    <CCACCTFROM>\
+                        <ACCTID>1111\
+                    </CCACCTFROM>\
+                    <BANKTRANLIST>\
+                        <DTSTART>20250715080000\
+                        <DTEND>20250716090000\
+                    </BANKTRANLIST>\
+                </CCSTMTRS>\
+            </CCSTMTTRNRS>\
+            <CCSTMTTRNRS>\
+                <TRNUID>2\
+                <CCSTMTRS>\
+                    <CCACCTFROM>\
+                        <ACCTID>2222\
+                    </CCACCTFROM>\
+                    <BANKTRANLIST>\
+                        <DTSTART>20250715080000\
+                        <DTEND>20250716090000\
+                    </BANKTRANLIST>\
+                </CCSTMTRS>\
+            </CCSTMTTRNRS>\
+            </CREDITCARDMSGSRSV1>";
+        let mut tokens = tokenize(input);
+
+        let ccmsgsrsv1 = CCMsgSrsV1::parse(&mut tokens).unwrap();
+        assert_eq!(ccmsgsrsv1.ccstmttrns.len(), 2);
+        assert_eq!(ccmsgsrsv1.ccstmttrns[0].trnuid, Some("1".to_string()));
+        assert_eq!(
+            ccmsgsrsv1.ccstmttrns[0]
+                .ccstmtrs
+                .as_ref()
+                .unwrap()
+                .ccacctfrom
+                .acct_id,
+            "1111"
+        );
+        assert_eq!(ccmsgsrsv1.ccstmttrns[1].trnuid, Some("2".to_string()));
+        assert_eq!(
+            ccmsgsrsv1.ccstmttrns[1]
+                .ccstmtrs
+                .as_ref()
+                .unwrap()
+                .ccacctfrom
+                .acct_id,
+            "2222"
+        );
+    }
+
     #[test]
     fn test_ccmsgsrsv1_missing_ccstmttrnrs() {
         let input = "</CREDITCARDMSGSRSV1>";
@@ -631,7 +995,7 @@ mod test_ccmsgsrsv1 {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("CCSTMTTRNRS is a requied value")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("CCSTMTTRNRS is a required value")
         ));
     }
 
@@ -644,7 +1008,7 @@ mod test_ccmsgsrsv1 {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedToken(msg)) if msg.contains("UNKNOWNTAG") && msg.contains("CREDITCARDMSGSRSV1")
+            Err(QFXParsingError::UnexpectedToken { message: msg, .. }) if msg.contains("UNKNOWNTAG") && msg.contains("CREDITCARDMSGSRSV1")
         ));
     }
 
@@ -668,7 +1032,7 @@ mod test_ccmsgsrsv1 {
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(QFXParsingError::UnexpectedEOF(msg)) if msg.contains("Was still expecting the '/CREDITCARDMSGSRSV1' token")
+            Err(QFXParsingError::UnexpectedEOF { message: msg, .. }) if msg.contains("Was still expecting the '/CREDITCARDMSGSRSV1' token")
         ));
     }
 }
@@ -0,0 +1,445 @@
+use chrono::DateTime;
+use chrono::FixedOffset;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::NaiveTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use std::fmt::Display;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A datetime read from an OFX file, like DTPOSTED or DTSERVER.
+///
+/// OFX datetimes can end with the offset from UTC and the name of the timezone they were
+/// written in, eg. `20250725193000[-5:EST]`. The offset is applied, so `utc()` gives the actual
+/// moment in time while `local()` gives the time as the financial institution wrote it. Values
+/// without an offset are in UTC as the spec says.
+///
+/// The spec allows anything from just a date to a time with milliseconds. The precision the
+/// value was written with is kept, so a date-only value can be told apart from midnight.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OfxDateTime {
+    datetime: DateTime<FixedOffset>,
+    timezone: Option<String>,
+    precision: DateTimePrecision,
+}
+
+/// How precisely an OFX datetime was written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DateTimePrecision {
+    /// YYYYMMDD
+    Day,
+    /// YYYYMMDDHHMM
+    Minute,
+    /// YYYYMMDDHHMMSS
+    Second,
+    /// YYYYMMDDHHMMSS.XXX
+    Millisecond,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseDateTimeError {
+    #[error("{0}")]
+    InvalidFormat(#[from] chrono::ParseError),
+    #[error("invalid timezone offset '{0}'")]
+    InvalidOffset(String),
+    #[error("invalid fractional seconds '{0}', expecting exactly 3 digits")]
+    InvalidFraction(String),
+}
+
+impl OfxDateTime {
+    /// The moment in time in UTC.
+    pub fn utc(&self) -> DateTime<Utc> {
+        self.datetime.with_timezone(&Utc)
+    }
+
+    /// The datetime in the offset it was written in. Same as `utc()` when the file did not
+    /// give an offset.
+    pub fn local(&self) -> DateTime<FixedOffset> {
+        self.datetime
+    }
+
+    /// The name of the timezone given after the offset. Eg. EST for `[-5:EST]`.
+    pub fn timezone(&self) -> Option<&str> {
+        self.timezone.as_deref()
+    }
+
+    pub fn precision(&self) -> DateTimePrecision {
+        self.precision
+    }
+
+    /// The date as it was written in the file, without converting it to UTC. This is the
+    /// value to use for date-only values.
+    pub fn date(&self) -> NaiveDate {
+        self.datetime.date_naive()
+    }
+}
+
+impl FromStr for OfxDateTime {
+    type Err = ParseDateTimeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_ofx_datetime(s)
+    }
+}
+
+impl Display for OfxDateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.precision {
+            DateTimePrecision::Day => write!(f, "{}", self.date())?,
+            DateTimePrecision::Minute => {
+                write!(f, "{}", self.datetime.format("%Y-%m-%d %H:%M %:z"))?
+            }
+            DateTimePrecision::Second | DateTimePrecision::Millisecond => {
+                write!(f, "{}", self.datetime)?
+            }
+        }
+        match &self.timezone {
+            Some(timezone) => write!(f, " {}", timezone),
+            None => Ok(()),
+        }
+    }
+}
+
+impl PartialEq<DateTime<Utc>> for OfxDateTime {
+    fn eq(&self, other: &DateTime<Utc>) -> bool {
+        self.datetime == *other
+    }
+}
+
+impl From<OfxDateTime> for DateTime<Utc> {
+    fn from(value: OfxDateTime) -> Self {
+        value.utc()
+    }
+}
+
+// Parses a date time in the OFX standard format. Expects something like: 20250725143000[-5:EST], 20250725T143000[+5.30:IST],
+// 20250725T143000Z, 20250725143000.123, 202507251430, 20250725
+pub(crate) fn parse_ofx_datetime(s: &str) -> Result<OfxDateTime, ParseDateTimeError> {
+    let (s, offset, timezone) = match s.rfind('[') {
+        Some(pos) => {
+            let (offset, timezone) = parse_timezone(&s[pos..])?;
+            (&s[..pos], offset, timezone)
+        }
+        None => (s, FixedOffset::east_opt(0).unwrap(), None),
+    };
+    let s = s.strip_suffix("Z").unwrap_or(s);
+
+    let (naive, precision) = parse_naive(s)?;
+    let datetime = offset
+        .from_local_datetime(&naive)
+        .single()
+        .ok_or_else(|| ParseDateTimeError::InvalidOffset(offset.to_string()))?;
+    Ok(OfxDateTime {
+        datetime,
+        timezone,
+        precision,
+    })
+}
+
+// Parses the datetime without the timezone. The date and time can be separated by a T
+fn parse_naive(s: &str) -> Result<(NaiveDateTime, DateTimePrecision), ParseDateTimeError> {
+    let compact = match s.char_indices().nth(8) {
+        Some((8, 'T')) if s.len() > 9 => format!("{}{}", &s[..8], &s[9..]),
+        _ => s.to_string(),
+    };
+
+    // The format is picked by the shape of the value so that chrono can report what is wrong
+    // with it when it does not match
+    if compact.len() == 8 {
+        let date = NaiveDate::parse_from_str(&compact, "%Y%m%d")?;
+        return Ok((date.and_time(NaiveTime::MIN), DateTimePrecision::Day));
+    }
+    if compact.len() == 12 {
+        let dt = NaiveDateTime::parse_from_str(&compact, "%Y%m%d%H%M")?;
+        return Ok((dt, DateTimePrecision::Minute));
+    }
+    if let Some((_, fraction)) = compact.split_once('.') {
+        let dt = NaiveDateTime::parse_from_str(&compact, "%Y%m%d%H%M%S%.f")?;
+        // The spec only has milliseconds, so any other number of digits is not a precision
+        // that can be recorded
+        if fraction.len() != 3 {
+            return Err(ParseDateTimeError::InvalidFraction(fraction.to_string()));
+        }
+        return Ok((dt, DateTimePrecision::Millisecond));
+    }
+    let dt = NaiveDateTime::parse_from_str(&compact, "%Y%m%d%H%M%S")?;
+    Ok((dt, DateTimePrecision::Second))
+}
+
+// Parses the bracketed timezone at the end of a datetime. Eg. [-5:EST] or [+5.30:IST]. The
+// name is optional. The offset is in hours and can have a fractional part, where two digits
+// are minutes (+5.30 is 5 hours 30 minutes) and a single digit is tenths of an hour (+5.5 is
+// also 5 hours 30 minutes).
+fn parse_timezone(s: &str) -> Result<(FixedOffset, Option<String>), ParseDateTimeError> {
+    let invalid = || ParseDateTimeError::InvalidOffset(s.to_string());
+    let inner = s
+        .strip_prefix('[')
+        .and_then(|x| x.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (offset, timezone) = match inner.split_once(':') {
+        Some((offset, timezone)) => (offset.trim(), Some(timezone.trim())),
+        None => (inner.trim(), None),
+    };
+
+    let (sign, offset) = match offset.as_bytes().first() {
+        Some(b'-') => (-1, &offset[1..]),
+        Some(b'+') => (1, &offset[1..]),
+        _ => (1, offset),
+    };
+    let (hours, fraction) = offset.split_once('.').unwrap_or((offset, ""));
+    if hours.is_empty() || !hours.bytes().all(|x| x.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    if !fraction.bytes().all(|x| x.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = match fraction.len() {
+        0 => 0,
+        1 => fraction.parse::<i32>().map_err(|_| invalid())? * 6,
+        2 => fraction.parse().map_err(|_| invalid())?,
+        _ => return Err(invalid()),
+    };
+    if hours >= 24 || minutes >= 60 {
+        return Err(invalid());
+    }
+
+    let offset = FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)?;
+    let timezone = timezone.filter(|x| !x.is_empty()).map(|x| x.to_string());
+    Ok((offset, timezone))
+}
+
+#[cfg(test)]
+mod datetime_tests {
+    use super::*;
+
+    #[test]
+    fn test_datetime_parser_valid_basic() {
+        // Basic OFX datetime without timezone or T
+        let dt = parse_ofx_datetime("20250725143000").unwrap();
+        let correct_dt = Utc.with_ymd_and_hms(2025, 7, 25, 14, 30, 0).unwrap();
+        assert_eq!(dt, correct_dt);
+    }
+
+    #[test]
+    fn test_datetime_parser_valid_with_t() {
+        // OFX datetime with T separator
+        let dt = parse_ofx_datetime("20250725T143000").unwrap();
+        let correct_dt = Utc.with_ymd_and_hms(2025, 7, 25, 14, 30, 0).unwrap();
+        assert_eq!(dt, correct_dt);
+    }
+
+    #[test]
+    fn test_datetime_parser_valid_with_z() {
+        // OFX datetime with Z suffix
+        let dt = parse_ofx_datetime("20250725T143000Z").unwrap();
+        let correct_dt = Utc.with_ymd_and_hms(2025, 7, 25, 14, 30, 0).unwrap();
+        assert_eq!(dt, correct_dt);
+    }
+
+    #[test]
+    fn test_datetime_parser_valid_with_bracketed() {
+        // OFX datetime with bracketed timezone info
+        let dt = parse_ofx_datetime("20250725143000[+7:PDT]").unwrap();
+        let correct_dt = Utc.with_ymd_and_hms(2025, 7, 25, 7, 30, 0).unwrap();
+        assert_eq!(dt, correct_dt);
+        assert_eq!(dt.timezone(), Some("PDT"));
+    }
+
+    #[test]
+    fn test_datetime_parser_valid_with_t_and_bracketed() {
+        // OFX datetime with T and bracketed timezone info
+        let dt = parse_ofx_datetime("20250725T143000[+7:PDT]").unwrap();
+        let correct_dt = Utc.with_ymd_and_hms(2025, 7, 25, 7, 30, 0).unwrap();
+        assert_eq!(dt, correct_dt);
+    }
+
+    #[test]
+    fn test_datetime_parser_invalid() {
+        // Invalid format should return error
+        let dt = parse_ofx_datetime("invalid-date-string");
+        assert!(dt.is_err());
+    }
+
+    #[test]
+    fn test_datetime_parser_applies_offset() {
+        // A transaction late in the evening in New York is on the next day in UTC
+        let dt = parse_ofx_datetime("20250731213000.000[-5:EST]").unwrap();
+        assert_eq!(
+            dt.utc(),
+            Utc.with_ymd_and_hms(2025, 8, 1, 2, 30, 0).unwrap()
+        );
+        assert_eq!(
+            dt.local(),
+            FixedOffset::west_opt(5 * 3600)
+                .unwrap()
+                .with_ymd_and_hms(2025, 7, 31, 21, 30, 0)
+                .unwrap()
+        );
+        assert_eq!(dt.local().offset().local_minus_utc(), -5 * 3600);
+        assert_eq!(dt.timezone(), Some("EST"));
+        assert_eq!(dt.to_string(), "2025-07-31 21:30:00 -05:00 EST");
+    }
+
+    #[test]
+    fn test_datetime_parser_fractional_offset() {
+        let expected = Utc.with_ymd_and_hms(2025, 7, 25, 9, 0, 0).unwrap();
+        for value in ["20250725143000[+5.30:IST]", "20250725143000[+5.5:IST]"] {
+            let dt = parse_ofx_datetime(value).unwrap();
+            assert_eq!(dt, expected);
+            assert_eq!(dt.local().offset().local_minus_utc(), 5 * 3600 + 30 * 60);
+        }
+
+        let dt = parse_ofx_datetime("20250725143000[-3.30:NST]").unwrap();
+        assert_eq!(
+            dt.utc(),
+            Utc.with_ymd_and_hms(2025, 7, 25, 18, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_datetime_parser_offset_without_name() {
+        let dt = parse_ofx_datetime("20250725143000[0]").unwrap();
+        assert_eq!(dt, Utc.with_ymd_and_hms(2025, 7, 25, 14, 30, 0).unwrap());
+        assert!(dt.timezone().is_none());
+
+        let dt = parse_ofx_datetime("20250725143000[9:]").unwrap();
+        assert_eq!(dt, Utc.with_ymd_and_hms(2025, 7, 25, 5, 30, 0).unwrap());
+        assert!(dt.timezone().is_none());
+    }
+
+    #[test]
+    fn test_datetime_parser_without_offset_is_utc() {
+        let dt = parse_ofx_datetime("20250725143000").unwrap();
+        assert_eq!(dt.local().offset().local_minus_utc(), 0);
+        assert!(dt.timezone().is_none());
+        assert_eq!(dt.utc(), DateTime::<Utc>::from(dt.clone()));
+    }
+
+    #[test]
+    fn test_datetime_parser_invalid_offset() {
+        for value in [
+            "20250725143000[:EST]",
+            "20250725143000[-5x:EST]",
+            "20250725143000[+25:XXX]",
+            "20250725143000[+5.75:XXX]",
+            "20250725143000[+5.123:XXX]",
+            "20250725143000[-5:EST",
+        ] {
+            assert!(
+                matches!(
+                    parse_ofx_datetime(value),
+                    Err(ParseDateTimeError::InvalidOffset(_))
+                ),
+                "Expected {} to be rejected",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_datetime_parser_precisions() {
+        let cases = [
+            ("20250725", DateTimePrecision::Day, (0, 0, 0), 0),
+            ("202507251430", DateTimePrecision::Minute, (14, 30, 0), 0),
+            ("20250725T1430", DateTimePrecision::Minute, (14, 30, 0), 0),
+            ("20250725143005", DateTimePrecision::Second, (14, 30, 5), 0),
+            (
+                "20250725T143005Z",
+                DateTimePrecision::Second,
+                (14, 30, 5),
+                0,
+            ),
+            (
+                "20250725143005.250",
+                DateTimePrecision::Millisecond,
+                (14, 30, 5),
+                250,
+            ),
+            (
+                "20250725T143005.250",
+                DateTimePrecision::Millisecond,
+                (14, 30, 5),
+                250,
+            ),
+        ];
+        for (value, precision, (h, m, sec), ms) in cases {
+            let dt = parse_ofx_datetime(value).unwrap();
+            assert_eq!(dt.precision(), precision, "Wrong precision for {}", value);
+            let expected = Utc.with_ymd_and_hms(2025, 7, 25, h, m, sec).unwrap()
+                + chrono::Duration::milliseconds(ms);
+            assert_eq!(dt, expected, "Wrong datetime for {}", value);
+        }
+    }
+
+    #[test]
+    fn test_datetime_parser_precisions_with_timezone() {
+        let dt = parse_ofx_datetime("20250725[-5:EST]").unwrap();
+        assert_eq!(dt.precision(), DateTimePrecision::Day);
+        assert_eq!(dt.date(), NaiveDate::from_ymd_opt(2025, 7, 25).unwrap());
+        assert_eq!(
+            dt.utc(),
+            Utc.with_ymd_and_hms(2025, 7, 25, 5, 0, 0).unwrap()
+        );
+
+        let dt = parse_ofx_datetime("202507252330[-5:EST]").unwrap();
+        assert_eq!(dt.precision(), DateTimePrecision::Minute);
+        assert_eq!(
+            dt.utc(),
+            Utc.with_ymd_and_hms(2025, 7, 26, 4, 30, 0).unwrap()
+        );
+        assert_eq!(dt.date(), NaiveDate::from_ymd_opt(2025, 7, 25).unwrap());
+
+        let dt = parse_ofx_datetime("20250725143005.123[+9:JST]").unwrap();
+        assert_eq!(dt.precision(), DateTimePrecision::Millisecond);
+        assert_eq!(dt.local().timestamp_subsec_millis(), 123);
+        assert_eq!(dt.timezone(), Some("JST"));
+    }
+
+    #[test]
+    fn test_datetime_display_uses_precision() {
+        let display = |x: &str| parse_ofx_datetime(x).unwrap().to_string();
+        assert_eq!(display("20250725"), "2025-07-25");
+        assert_eq!(display("20250725[-5:EST]"), "2025-07-25 EST");
+        assert_eq!(display("202507251430"), "2025-07-25 14:30 +00:00");
+        assert_eq!(display("20250725143005"), "2025-07-25 14:30:05 +00:00");
+    }
+
+    #[test]
+    fn test_datetime_parser_fraction_must_be_milliseconds() {
+        for (value, fraction) in [
+            ("20250725143005.5", "5"),
+            ("20250725143005.123456[-5:EST]", "123456"),
+        ] {
+            assert_eq!(
+                parse_ofx_datetime(value),
+                Err(ParseDateTimeError::InvalidFraction(fraction.to_string())),
+                "Expected {} to be rejected",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_datetime_parser_invalid_precisions() {
+        for value in [
+            "2025072",
+            "2025072514",
+            "20250725143",
+            "20251325",
+            "20250725T",
+            "20250725.5",
+        ] {
+            assert!(
+                matches!(
+                    parse_ofx_datetime(value),
+                    Err(ParseDateTimeError::InvalidFormat(_))
+                ),
+                "Expected {} to be rejected",
+                value
+            );
+        }
+    }
+}
@@ -0,0 +1,410 @@
+use crate::AvailableBalance;
+use crate::Bal;
+use crate::BankTranList;
+use crate::Currency;
+use crate::LedgerBal;
+use crate::Parseable;
+use crate::Payee;
+use crate::QFX;
+use crate::QFXParsingError;
+use crate::Status;
+use crate::Stmttrn;
+use crate::balance::BALLIST_TAGS;
+use crate::bank_msg::BankMsgSrsV1;
+use crate::bank_msg::Bankacctfrom;
+use crate::bank_msg::Stmtrs;
+use crate::bank_msg::Stmttrnrs;
+use crate::credit_card::CCMsgSrsV1;
+use crate::credit_card::Ccacctfrom;
+use crate::credit_card::Ccstmtrs;
+use crate::credit_card::Ccstmttrnrs;
+use crate::credit_card::RewardInfo;
+use crate::sign_on::FinancialInstitution;
+use crate::sign_on::SignOnMsgSrsV1;
+use crate::sign_on::Sonrs;
+use std::fmt::Write;
+
+// The tags each aggregate knows about. Used to name the aggregate an error was found in and to
+// suggest the tag that was probably meant when an unknown one is found. The lists come from the
+// parsers so they can't drift apart.
+const KNOWN_TAGS: [(&str, &[&str]); 25] = [
+    ("OFX", QFX::TAGS),
+    ("SIGNONMSGSRSV1", SignOnMsgSrsV1::TAGS),
+    ("SONRS", Sonrs::TAGS),
+    ("FI", FinancialInstitution::TAGS),
+    ("STATUS", Status::TAGS),
+    ("BANKMSGSRSV1", BankMsgSrsV1::TAGS),
+    ("STMTTRNRS", Stmttrnrs::TAGS),
+    ("STMTRS", Stmtrs::TAGS),
+    ("BANKACCTFROM", Bankacctfrom::TAGS),
+    ("CREDITCARDMSGSRSV1", CCMsgSrsV1::TAGS),
+    ("CCSTMTTRNRS", Ccstmttrnrs::TAGS),
+    ("CCSTMTRS", Ccstmtrs::TAGS),
+    ("CCACCTFROM", Ccacctfrom::TAGS),
+    ("REWARDINFO", RewardInfo::TAGS),
+    ("BANKTRANLIST", BankTranList::TAGS),
+    ("STMTTRN", Stmttrn::TAGS),
+    ("BANKACCTTO", Bankacctfrom::TAGS),
+    ("CCACCTTO", Ccacctfrom::TAGS),
+    ("CURRENCY", Currency::TAGS),
+    ("ORIGCURRENCY", Currency::TAGS),
+    ("PAYEE", Payee::TAGS),
+    ("BALLIST", BALLIST_TAGS),
+    ("BAL", Bal::TAGS),
+    ("LEDGERBAL", LedgerBal::TAGS),
+    ("AVAILBAL", AvailableBalance::TAGS),
+];
+
+impl QFXParsingError {
+    /// Renders the error for a person to read. The offending line of the document is printed
+    /// with a caret under the bad token, followed by the aggregate that was being parsed and a
+    /// suggestion on how to fix the file where one is available.
+    ///
+    /// `source` has to be the text that was parsed. For files and bytes this is the decoded
+    /// contents, which `QFX::decode` returns.
+    pub fn render(&self, source: &str) -> String {
+        let mut output = format!("error: {}\n", self.message());
+        let Some(context) = self.context() else {
+            return output;
+        };
+        let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+
+        // The source from the start of the bad token to the end of its line
+        let mut token = None;
+        if let Some(position) = context
+            .position
+            .filter(|x| x.offset <= source.len() && source.is_char_boundary(x.offset))
+        {
+            let line_start = source[..position.offset].rfind('\n').map_or(0, |x| x + 1);
+            let line_end = source[position.offset..]
+                .find('\n')
+                .map_or(source.len(), |x| position.offset + x);
+            let line = source[line_start..line_end].trim_end_matches('\r');
+            let previous = source[..line_start.saturating_sub(1)]
+                .rsplit('\n')
+                .next()
+                .map(|x| x.trim_end_matches('\r'))
+                .filter(|x| position.line > 1 && !x.trim().is_empty());
+
+            let width = position.line.to_string().len();
+            let _ = writeln!(
+                output,
+                "{:width$}--> line {}, column {}",
+                "", position.line, position.column
+            );
+            let _ = writeln!(output, "{:width$} |", "");
+            if let Some(previous) = previous {
+                let _ = writeln!(output, "{:width$} | {}", position.line - 1, previous);
+            }
+            let _ = writeln!(output, "{:width$} | {}", position.line, line);
+            // Tabs are kept so the caret lines up with the line above it
+            let padding: String = source[line_start..position.offset]
+                .chars()
+                .map(|x| if x == '\t' { '\t' } else { ' ' })
+                .collect();
+            let caret = "^".repeat(token_width(&source[position.offset..line_end]));
+            let _ = writeln!(output, "{:width$} | {}{}", "", padding, caret);
+            token = Some(&source[position.offset..line_end]);
+        }
+
+        if let Some(aggregate) = aggregate(&context.path) {
+            let _ = writeln!(
+                output,
+                "note: found while parsing the {} aggregate ({})",
+                aggregate, context.path
+            );
+        }
+        if let Some(help) = self.help(aggregate(&context.path), token) {
+            let _ = writeln!(output, "help: {}", help);
+        }
+        output
+    }
+
+    fn help(&self, aggregate: Option<&str>, token: Option<&str>) -> Option<String> {
+        match self {
+            QFXParsingError::UnexpectedToken {
+                expected: Some(expected),
+                found: Some(found),
+                ..
+            } if expected.starts_with("a value for ") => {
+                let tag = &expected["a value for ".len()..];
+                Some(format!("<{}> needs a value before <{}>", tag, found))
+            }
+            QFXParsingError::UnexpectedToken {
+                found: Some(found), ..
+            } => {
+                let aggregate = aggregate?;
+                if let Some(name) = found.strip_prefix('/') {
+                    return Some(format!(
+                        "</{}> does not close the {} aggregate. Expected </{}>",
+                        name, aggregate, aggregate
+                    ));
+                }
+                // Tags that are known but not allowed where they are, and stray text, do not
+                // have a better tag to suggest
+                let known = KNOWN_TAGS.iter().find(|(x, _)| *x == aggregate)?.1;
+                if known.contains(&found.as_str()) || !token?.starts_with('<') {
+                    return None;
+                }
+                match closest(found, known) {
+                    Some(tag) => Some(format!("did you mean <{}>?", tag)),
+                    None => Some(format!(
+                        "<{}> is not a tag of {}. Expected one of: {}",
+                        found,
+                        aggregate,
+                        known.join(", ")
+                    )),
+                }
+            }
+            QFXParsingError::UnexpectedEOF { expected, .. } => Some(format!(
+                "the file ended while still expecting {}. It may have been cut short",
+                expected
+            )),
+            QFXParsingError::MissingRequiredValue { field, .. } => Some(format!(
+                "add a <{}> element to the {} aggregate",
+                field,
+                aggregate.unwrap_or("enclosing")
+            )),
+            QFXParsingError::UnexpectedDateFormat { .. } => Some(
+                "datetimes are written as YYYYMMDD, YYYYMMDDHHMMSS or YYYYMMDDHHMMSS.XXX[offset:TZ]. Eg. 20250725143000.000[-5:EST]"
+                    .to_string(),
+            ),
+            QFXParsingError::InvalidTransactionAmount { .. } => Some(
+                "amounts are written as a number with an optional sign and decimal point. Eg. -55.75"
+                    .to_string(),
+            ),
+            QFXParsingError::ServerError { code, .. } => code
+                .description()
+                .map(|x| format!("status code {} means: {}", code, x)),
+            QFXParsingError::InvalidSyntax { message, .. }
+                if message.contains('<') || message.contains('>') =>
+            {
+                Some("a < or > inside a value has to be written as &lt; or &gt;".to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+// The innermost aggregate in the path. Eg. STMTTRN for .../STMTTRN[3]/TRNAMT
+fn aggregate(path: &str) -> Option<&str> {
+    path.rsplit('/')
+        .map(|x| x.split('[').next().unwrap_or(x))
+        .find(|x| KNOWN_TAGS.iter().any(|(name, _)| name == x))
+}
+
+// How many characters to underline for the token at the start of the input
+fn token_width(input: &str) -> usize {
+    let width = if input.starts_with('<') {
+        input.find('>').map_or(input.len(), |x| x + 1)
+    } else {
+        input.find('<').unwrap_or(input.len())
+    };
+    input[..width].trim_end().chars().count().max(1)
+}
+
+// The known tag closest to the unknown one, if any of them is close enough to be a typo
+fn closest<'a>(unknown: &str, known: &[&'a str]) -> Option<&'a str> {
+    let unknown = unknown.to_ascii_uppercase();
+    known
+        .iter()
+        .map(|x| (edit_distance(&unknown, x), *x))
+        .filter(|(distance, x)| *distance <= (x.len() / 3).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, x)| x)
+}
+
+// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != *y);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod diagnostics_tests {
+    use super::*;
+    use crate::ParseOptions;
+    use crate::balance::parse_ballist;
+    use crate::tokenize;
+
+    const DOCUMENT: &str = "<OFX>
+<BANKMSGSRSV1>
+<STMTTRNRS>
+<STMTRS>
+<BANKTRANLIST>
+<DTSTART>20250701120000
+<DTEND>20250731120000
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20250725120000
+<TRNAMNT>-1.00
+</STMTTRN>
+</BANKTRANLIST>
+</STMTRS>
+</STMTTRNRS>
+</BANKMSGSRSV1>
+</OFX>";
+
+    #[test]
+    fn test_render_unknown_tag() {
+        let error = DOCUMENT.parse::<QFX>().err().unwrap();
+        let rendered = error.render(DOCUMENT);
+        assert_eq!(
+            rendered,
+            "error: Found unexpected token TRNAMNT in the STMTTRN type
+  --> line 11, column 1
+   |
+10 | <DTPOSTED>20250725120000
+11 | <TRNAMNT>-1.00
+   | ^^^^^^^^^
+note: found while parsing the STMTTRN aggregate (OFX/BANKMSGSRSV1/STMTTRNRS[0]/STMTRS/BANKTRANLIST/STMTTRN[0]/TRNAMNT)
+help: did you mean <TRNAMT>?
+"
+        );
+    }
+
+    #[test]
+    fn test_render_invalid_value() {
+        let input = DOCUMENT.replace("<TRNAMNT>-1.00", "<TRNAMT>  -1.0O");
+        let error = input.parse::<QFX>().err().unwrap();
+        let rendered = error.render(&input);
+        assert!(rendered.contains("11 | <TRNAMT>  -1.0O\n   |           ^^^^^\n"));
+        assert!(rendered.contains("the STMTTRN aggregate"));
+        assert!(rendered.contains("help: amounts are written as"));
+    }
+
+    #[test]
+    fn test_render_missing_value() {
+        let input = DOCUMENT.replace("<TRNAMNT>-1.00\n", "<TRNAMT>-1.00\n<FITID>1\n");
+        let error = input.parse::<QFX>().err().unwrap();
+        let rendered = error.render(&input);
+        assert!(rendered.contains("13 | </STMTTRN>\n   | ^^^^^^^^^^\n"));
+        assert!(rendered.contains("help: add a <NAME> element to the STMTTRN aggregate"));
+    }
+
+    #[test]
+    fn test_render_missing_leaf_value() {
+        let input = DOCUMENT.replace("<TRNAMNT>-1.00\n", "<MEMO>\n");
+        let rendered = input.parse::<QFX>().err().unwrap().render(&input);
+        assert!(rendered.contains("12 | </STMTTRN>\n   | ^^^^^^^^^^\n"));
+        assert!(rendered.contains("help: <MEMO> needs a value before </STMTTRN>\n"));
+
+        let input = DOCUMENT.replace("<TRNAMNT>-1.00\n", "<MEMO><NAME>A\n");
+        let rendered = input.parse::<QFX>().err().unwrap().render(&input);
+        assert!(rendered.contains("help: <MEMO> needs a value before <NAME>\n"));
+    }
+
+    #[test]
+    fn test_render_stray_text() {
+        // Text is not a misspelled tag even if it looks like one
+        let input = DOCUMENT.replace("<TRNAMNT>-1.00\n", "<TRNAMT>-1.00</TRNAMT>TRNAMNT\n");
+        let rendered = input.parse::<QFX>().err().unwrap().render(&input);
+        assert!(rendered.contains(&format!("   | {}^^^^^^^\n", " ".repeat(22))));
+        assert!(!rendered.contains("help:"));
+    }
+
+    #[test]
+    fn test_render_windows_1252() {
+        // The é in the NAME is a single byte that is not valid UTF-8
+        let text = format!(
+            "OFXHEADER:100\nDATA:OFXSGML\nVERSION:102\nCHARSET:1252\n\n{}",
+            DOCUMENT.replace("<TRNAMNT>-1.00", "<NAME>CAF\u{e9}\n<TRNAMNT>-1.00")
+        );
+        let (bytes, _, _) = encoding_rs::WINDOWS_1252.encode(&text);
+        let options = ParseOptions::default();
+        let error = QFX::from_bytes_with_options(&bytes, &options)
+            .err()
+            .unwrap();
+        let source = QFX::decode(&bytes, &options).unwrap();
+        let rendered = error.render(&source);
+        assert!(rendered.contains("17 | <TRNAMNT>-1.00\n   | ^^^^^^^^^\n"));
+        assert!(rendered.contains("16 | <NAME>CAF\u{e9}\n"));
+    }
+
+    #[test]
+    fn test_render_without_context() {
+        let error = QFX::from_path("tests/data/does_not_exist.qfx")
+            .err()
+            .unwrap();
+        let rendered = error.render("");
+        assert_eq!(rendered.lines().count(), 1);
+        assert!(rendered.starts_with("error: "));
+    }
+
+    #[test]
+    fn test_known_tags_are_accepted() {
+        // Parses a start tag inside the aggregate. The input ends right after it, so this is
+        // an EOF error unless the parser does not know the tag.
+        fn parse(aggregate: &str, tag: &str) -> QFXParsingError {
+            let input = format!("<{}>", tag);
+            let tokens = &mut tokenize(&input);
+            let result = match aggregate {
+                "OFX" => QFX::parse(tokens).map(drop),
+                "SIGNONMSGSRSV1" => SignOnMsgSrsV1::parse(tokens).map(drop),
+                "SONRS" => Sonrs::parse(tokens).map(drop),
+                "FI" => FinancialInstitution::parse(tokens).map(drop),
+                "STATUS" => Status::parse(tokens).map(drop),
+                "BANKMSGSRSV1" => BankMsgSrsV1::parse(tokens).map(drop),
+                "STMTTRNRS" => Stmttrnrs::parse(tokens).map(drop),
+                "STMTRS" => Stmtrs::parse(tokens).map(drop),
+                "BANKACCTFROM" | "BANKACCTTO" => {
+                    Bankacctfrom::parse_aggregate(tokens, aggregate).map(drop)
+                }
+                "CREDITCARDMSGSRSV1" => CCMsgSrsV1::parse(tokens).map(drop),
+                "CCSTMTTRNRS" => Ccstmttrnrs::parse(tokens).map(drop),
+                "CCSTMTRS" => Ccstmtrs::parse(tokens).map(drop),
+                "CCACCTFROM" | "CCACCTTO" => {
+                    Ccacctfrom::parse_aggregate(tokens, aggregate).map(drop)
+                }
+                "REWARDINFO" => RewardInfo::parse(tokens).map(drop),
+                "BANKTRANLIST" => BankTranList::parse(tokens).map(drop),
+                "STMTTRN" => Stmttrn::parse(tokens).map(drop),
+                "CURRENCY" | "ORIGCURRENCY" => {
+                    Currency::parse_aggregate(tokens, aggregate).map(drop)
+                }
+                "PAYEE" => Payee::parse(tokens).map(drop),
+                "BALLIST" => parse_ballist(tokens).map(drop),
+                "BAL" => Bal::parse(tokens).map(drop),
+                "LEDGERBAL" => LedgerBal::parse(tokens).map(drop),
+                "AVAILBAL" => AvailableBalance::parse(tokens).map(drop),
+                _ => panic!("No parser for the {} aggregate", aggregate),
+            };
+            result.err().unwrap()
+        }
+        let rejects = |aggregate: &str, tag: &str| {
+            matches!(
+                parse(aggregate, tag),
+                QFXParsingError::UnexpectedToken { found: Some(found), .. } if found == tag
+            )
+        };
+
+        for (aggregate, tags) in KNOWN_TAGS {
+            for tag in tags {
+                assert!(
+                    !rejects(aggregate, tag),
+                    "{} is listed for {} but its parser does not accept it",
+                    tag,
+                    aggregate
+                );
+            }
+            assert!(rejects(aggregate, "INTU.XYZ"));
+        }
+    }
+
+    #[test]
+    fn test_closest_tag() {
+        let known = KNOWN_TAGS.iter().find(|(x, _)| *x == "STMTTRN").unwrap().1;
+        assert_eq!(closest("TRNAMNT", known), Some("TRNAMT"));
+        assert_eq!(closest("memo", known), Some("MEMO"));
+        assert_eq!(closest("INVBUY", known), None);
+    }
+}
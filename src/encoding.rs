@@ -0,0 +1,240 @@
+use crate::OfxHeader;
+use crate::QFXParsingError;
+use std::borrow::Cow;
+
+/// The character encodings a QFX file can be decoded from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// CHARSET:1252. Also used for USASCII files since it is a superset of ASCII.
+    Windows1252,
+    /// CHARSET:ISO-8859-1
+    Latin1,
+}
+
+impl Encoding {
+    // Works out the encoding from the ENCODING and CHARSET values of the header. Returns None
+    // if the header does not say anything useful about the encoding.
+    pub(crate) fn from_header(header: &OfxHeader) -> Result<Option<Self>, QFXParsingError> {
+        // CHARSET only matters when ENCODING is USASCII. A Unicode ENCODING wins over it.
+        let encoding = header.encoding.as_deref().map(|x| x.to_ascii_uppercase());
+        match encoding.as_deref() {
+            // OFX 1.6 uses UNICODE to mean UTF-8
+            Some("UTF-8" | "UTF8" | "UNICODE") => return Ok(Some(Encoding::Utf8)),
+            Some("UTF-16" | "UTF-16LE") => return Ok(Some(Encoding::Utf16Le)),
+            Some("UTF-16BE") => return Ok(Some(Encoding::Utf16Be)),
+            Some("WINDOWS-1252") => return Ok(Some(Encoding::Windows1252)),
+            Some("ISO-8859-1" | "LATIN1") => return Ok(Some(Encoding::Latin1)),
+            Some("USASCII" | "US-ASCII" | "ASCII") | None => {}
+            Some(encoding) => {
+                return Err(QFXParsingError::UnsupportedHeaderValue {
+                    message: format!("ENCODING:{} is not supported", encoding),
+                });
+            }
+        }
+
+        let charset = header.charset.as_deref().map(|x| x.to_ascii_uppercase());
+        match charset.as_deref() {
+            Some("1252" | "WINDOWS-1252" | "CP1252") => Ok(Some(Encoding::Windows1252)),
+            Some("ISO-8859-1" | "8859-1" | "ISO8859-1" | "LATIN1" | "LATIN-1") => {
+                Ok(Some(Encoding::Latin1))
+            }
+            Some("UTF-8" | "UTF8") => Ok(Some(Encoding::Utf8)),
+            // A USASCII file is windows-1252 unless the CHARSET says otherwise
+            _ if encoding.is_some() && charset.as_deref().is_none_or(|x| x == "NONE") => {
+                Ok(Some(Encoding::Windows1252))
+            }
+            // Files carry all sorts of CHARSET values. Eg. CSUNICODE or 8859-15. Rather than
+            // failing, the encoding is worked out from the bytes like for an undeclared file.
+            _ => Ok(None),
+        }
+    }
+
+    // Checks the start of the input for a byte order mark. Returns the encoding and the length
+    // of the mark.
+    fn from_bom(bytes: &[u8]) -> Option<(Self, usize)> {
+        match bytes {
+            [0xEF, 0xBB, 0xBF, ..] => Some((Encoding::Utf8, 3)),
+            [0xFF, 0xFE, ..] => Some((Encoding::Utf16Le, 2)),
+            [0xFE, 0xFF, ..] => Some((Encoding::Utf16Be, 2)),
+            _ => None,
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> Result<Cow<'_, str>, QFXParsingError> {
+        let encoding = match self {
+            Encoding::Utf8 => {
+                return std::str::from_utf8(bytes).map(Cow::Borrowed).map_err(|e| {
+                    QFXParsingError::InvalidEncoding {
+                        message: format!("The file is not valid UTF-8: {}", e),
+                    }
+                });
+            }
+            // encoding_rs treats ISO-8859-1 as windows-1252, so it is decoded by hand instead.
+            Encoding::Latin1 => return Ok(encoding_rs::mem::decode_latin1(bytes)),
+            Encoding::Utf16Le => encoding_rs::UTF_16LE,
+            Encoding::Utf16Be => encoding_rs::UTF_16BE,
+            Encoding::Windows1252 => encoding_rs::WINDOWS_1252,
+        };
+        encoding
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .ok_or(QFXParsingError::InvalidEncoding {
+                message: format!("The file is not valid {}", encoding.name()),
+            })
+    }
+}
+
+// Decodes the raw bytes of a file in to a string. A forced encoding always wins, followed by a
+// byte order mark and then the ENCODING/CHARSET values in the header. Files that say nothing
+// about their encoding are read as UTF-8, falling back to windows-1252 which is what most
+// banks actually send.
+pub(crate) fn decode(
+    bytes: &[u8],
+    forced: Option<Encoding>,
+) -> Result<Cow<'_, str>, QFXParsingError> {
+    let (bom, bytes) = match Encoding::from_bom(bytes) {
+        Some((encoding, length)) => (Some(encoding), &bytes[length..]),
+        None => (None, bytes),
+    };
+    if let Some(encoding) = forced.or(bom) {
+        return encoding.decode(bytes);
+    }
+
+    // The header is always ASCII so it can be read before the rest of the file is decoded.
+    // Any problems with it are reported once the decoded file is parsed.
+    let declared = bytes
+        .windows(5)
+        .position(|x| x == b"<OFX>")
+        .and_then(|x| OfxHeader::parse(&String::from_utf8_lossy(&bytes[..x])).ok())
+        .map(|x| Encoding::from_header(&x))
+        .transpose()?
+        .flatten();
+    match declared {
+        Some(encoding) => encoding.decode(bytes),
+        None => Encoding::Utf8
+            .decode(bytes)
+            .or_else(|_| Encoding::Windows1252.decode(bytes)),
+    }
+}
+
+#[cfg(test)]
+mod encoding_tests {
+    use super::*;
+
+    const SGML_HEADER: &str = "OFXHEADER:100\nDATA:OFXSGML\nVERSION:102\n";
+
+    #[test]
+    fn test_decode_windows_1252_from_charset() {
+        let mut input =
+            format!("{}ENCODING:USASCII\nCHARSET:1252\n<OFX>", SGML_HEADER).into_bytes();
+        input.extend_from_slice(b"<NAME>CAF\xC9 \x92 </OFX>");
+        let decoded = decode(&input, None).unwrap();
+        assert!(decoded.ends_with("<NAME>CAFÉ ’ </OFX>"));
+    }
+
+    #[test]
+    fn test_decode_latin1_from_charset() {
+        let mut input = format!("{}CHARSET:ISO-8859-1\n<OFX>", SGML_HEADER).into_bytes();
+        input.extend_from_slice(b"<NAME>CAF\xE9</OFX>");
+        let decoded = decode(&input, None).unwrap();
+        assert!(decoded.ends_with("<NAME>CAFé</OFX>"));
+    }
+
+    #[test]
+    fn test_decode_utf8_from_encoding() {
+        let input = format!(
+            "{}ENCODING:UTF-8\nCHARSET:NONE\n<OFX><NAME>CAFÉ</OFX>",
+            SGML_HEADER
+        );
+        let decoded = decode(input.as_bytes(), None).unwrap();
+        assert!(decoded.ends_with("<NAME>CAFÉ</OFX>"));
+    }
+
+    #[test]
+    fn test_decode_invalid_utf8_from_encoding() {
+        let mut input = format!("{}ENCODING:UTF-8\nCHARSET:NONE\n<OFX>", SGML_HEADER).into_bytes();
+        input.extend_from_slice(b"<NAME>CAF\xC9</OFX>");
+        let result = decode(&input, None);
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::InvalidEncoding { .. })
+        ));
+    }
+
+    #[test]
+    fn test_decode_unknown_charset_is_detected() {
+        let input = format!("{}CHARSET:CSUNICODE\n<OFX><NAME>CAFÉ</OFX>", SGML_HEADER);
+        let decoded = decode(input.as_bytes(), None).unwrap();
+        assert!(decoded.ends_with("<NAME>CAFÉ</OFX>"));
+
+        let mut input = format!("{}CHARSET:8859-15\n<OFX>", SGML_HEADER).into_bytes();
+        input.extend_from_slice(b"<NAME>CAF\xC9</OFX>");
+        let decoded = decode(&input, None).unwrap();
+        assert!(decoded.ends_with("<NAME>CAFÉ</OFX>"));
+    }
+
+    #[test]
+    fn test_decode_unicode_encoding_ignores_charset() {
+        let input = format!(
+            "{}ENCODING:UTF-8\nCHARSET:1252\n<OFX><NAME>CAFÉ ’</OFX>",
+            SGML_HEADER
+        );
+        let decoded = decode(input.as_bytes(), None).unwrap();
+        assert!(decoded.ends_with("<NAME>CAFÉ ’</OFX>"));
+    }
+
+    #[test]
+    fn test_decode_unsupported_encoding() {
+        let input = format!("{}ENCODING:EBCDIC\n<OFX></OFX>", SGML_HEADER);
+        let result = decode(input.as_bytes(), None);
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::UnsupportedHeaderValue { message: msg, .. }) if msg.contains("EBCDIC")
+        ));
+    }
+
+    #[test]
+    fn test_decode_xml_declaration() {
+        let mut input =
+            b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><?OFX OFXHEADER=\"200\" VERSION=\"211\"?><OFX>".to_vec();
+        input.extend_from_slice(b"<NAME>CAF\xE9</NAME></OFX>");
+        let decoded = decode(&input, None).unwrap();
+        assert!(decoded.ends_with("<NAME>CAFé</NAME></OFX>"));
+    }
+
+    #[test]
+    fn test_decode_utf8_bom() {
+        let input = b"\xEF\xBB\xBF<OFX></OFX>";
+        let decoded = decode(input, None).unwrap();
+        assert_eq!(decoded, "<OFX></OFX>");
+    }
+
+    #[test]
+    fn test_decode_utf16_bom() {
+        let mut input = vec![0xFF, 0xFE];
+        input.extend("<OFX>É</OFX>".encode_utf16().flat_map(|x| x.to_le_bytes()));
+        let decoded = decode(&input, None).unwrap();
+        assert_eq!(decoded, "<OFX>É</OFX>");
+
+        let mut input = vec![0xFE, 0xFF];
+        input.extend("<OFX>É</OFX>".encode_utf16().flat_map(|x| x.to_be_bytes()));
+        let decoded = decode(&input, None).unwrap();
+        assert_eq!(decoded, "<OFX>É</OFX>");
+    }
+
+    #[test]
+    fn test_decode_undeclared_falls_back_to_windows_1252() {
+        let decoded = decode(b"<OFX><NAME>CAF\xC9</OFX>", None).unwrap();
+        assert_eq!(decoded, "<OFX><NAME>CAFÉ</OFX>");
+    }
+
+    #[test]
+    fn test_decode_forced_encoding() {
+        // The header claims windows-1252 but the caller knows better
+        let input = format!("{}CHARSET:1252\n<OFX><NAME>CAFÉ</OFX>", SGML_HEADER);
+        let decoded = decode(input.as_bytes(), Some(Encoding::Utf8)).unwrap();
+        assert!(decoded.ends_with("<NAME>CAFÉ</OFX>"));
+    }
+}
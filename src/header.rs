@@ -0,0 +1,436 @@
+use crate::QFXParsingError;
+
+/// The header that precedes the `<OFX>` tag. OFX 1.x (SGML) files use KEY:VALUE lines, eg.
+///
+/// ```text
+/// OFXHEADER:100
+/// DATA:OFXSGML
+/// VERSION:102
+/// SECURITY:NONE
+/// ENCODING:USASCII
+/// CHARSET:1252
+/// COMPRESSION:NONE
+/// OLDFILEUID:NONE
+/// NEWFILEUID:NONE
+/// ```
+///
+/// while OFX 2.x (XML) files use processing instructions, eg.
+///
+/// ```text
+/// <?xml version="1.0" encoding="UTF-8" standalone="no"?>
+/// <?OFX OFXHEADER="200" VERSION="211" SECURITY="NONE" OLDFILEUID="NONE" NEWFILEUID="NONE"?>
+/// ```
+///
+/// Both are parsed into the same type. XML files have no DATA, CHARSET or COMPRESSION and
+/// their ENCODING comes from the `<?xml?>` declaration.
+///
+/// OFXHEADER and VERSION are the only values that are required here. Banks regularly leave
+/// the rest of them out even though the spec says they should be present.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OfxHeader {
+    pub ofx_header: u16,
+    pub data: Option<String>,
+    pub version: u16,
+    pub security: Option<String>,
+    pub encoding: Option<String>,
+    pub charset: Option<String>,
+    pub compression: Option<String>,
+    /// `None` when the file says NONE
+    pub old_file_uid: Option<String>,
+    /// `None` when the file says NONE. Stays the same if the same file is served twice so
+    /// it can be used to detect re-imports.
+    pub new_file_uid: Option<String>,
+    // Decided by the syntax of the header rather than the OFXHEADER value
+    flavour: OfxFlavour,
+}
+
+/// The two flavours of OFX documents. The flavour is decided by the header of the file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OfxFlavour {
+    /// OFX 1.x, where the end tags of leaf elements are optional
+    Sgml,
+    /// OFX 2.x, where every element is closed
+    Xml,
+}
+
+impl OfxHeader {
+    /// The flavour of the document this header belongs to. A `<?OFX?>` processing instruction
+    /// means XML and KEY:VALUE lines mean SGML, whatever the OFXHEADER says.
+    pub fn flavour(&self) -> OfxFlavour {
+        self.flavour
+    }
+
+    // Parses everything that comes before the <OFX> tag in the file. The flavour is detected
+    // from the first thing in the header: XML headers start with a processing instruction.
+    pub(crate) fn parse(header: &str) -> Result<Self, QFXParsingError> {
+        let header = header.trim_start();
+        let result = if header.starts_with("<?") {
+            Self::parse_xml(header)?
+        } else {
+            Self::parse_sgml(header)?
+        };
+        result.validate()?;
+        Ok(result)
+    }
+
+    // Parses the KEY:VALUE lines of an OFX 1.x header
+    fn parse_sgml(header: &str) -> Result<Self, QFXParsingError> {
+        let mut s_ofx_header = None;
+        let mut s_data = None;
+        let mut s_version = None;
+        let mut s_security = None;
+        let mut s_encoding = None;
+        let mut s_charset = None;
+        let mut s_compression = None;
+        let mut s_old_file_uid = None;
+        let mut s_new_file_uid = None;
+        for line in header.lines().map(|x| x.trim()).filter(|x| !x.is_empty()) {
+            let Some((key, value)) = line.split_once(':') else {
+                return Err(QFXParsingError::InvalidHeader {
+                    message: format!(
+                        "Expected a KEY:VALUE pair in the OFX header, Found {}",
+                        line
+                    ),
+                });
+            };
+            let value = value.trim();
+            match key.trim() {
+                "OFXHEADER" => s_ofx_header = Some(parse_header_number("OFXHEADER", value)?),
+                "DATA" => s_data = Some(value.to_string()),
+                "VERSION" => s_version = Some(parse_header_number("VERSION", value)?),
+                "SECURITY" => s_security = Some(value.to_string()),
+                "ENCODING" => s_encoding = Some(value.to_string()),
+                "CHARSET" => s_charset = Some(value.to_string()),
+                "COMPRESSION" => s_compression = Some(value.to_string()),
+                "OLDFILEUID" => s_old_file_uid = none_if_unset(value),
+                "NEWFILEUID" => s_new_file_uid = none_if_unset(value),
+                // Some banks add their own keys to the header. They don't change how the
+                // document is read so they are ignored.
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            ofx_header: s_ofx_header.ok_or(QFXParsingError::InvalidHeader {
+                message: "OFXHEADER is a required value in the OFX header".to_string(),
+            })?,
+            data: s_data,
+            version: s_version.ok_or(QFXParsingError::InvalidHeader {
+                message: "VERSION is a required value in the OFX header".to_string(),
+            })?,
+            security: s_security,
+            encoding: s_encoding,
+            charset: s_charset,
+            compression: s_compression,
+            old_file_uid: s_old_file_uid,
+            new_file_uid: s_new_file_uid,
+            flavour: OfxFlavour::Sgml,
+        })
+    }
+
+    // Parses the <?xml?> declaration and <?OFX?> processing instruction of an OFX 2.x header
+    fn parse_xml(header: &str) -> Result<Self, QFXParsingError> {
+        let mut s_ofx_header = None;
+        let mut s_version = None;
+        let mut s_security = None;
+        let mut s_encoding = None;
+        let mut s_old_file_uid = None;
+        let mut s_new_file_uid = None;
+        let mut rest = header.trim();
+        while !rest.is_empty() {
+            let Some(instruction) = rest.strip_prefix("<?") else {
+                return Err(QFXParsingError::InvalidHeader {
+                    message: format!(
+                        "Expected a processing instruction in the OFX header, Found {}",
+                        rest
+                    ),
+                });
+            };
+            let Some(end_index) = instruction.find("?>") else {
+                return Err(QFXParsingError::InvalidHeader {
+                    message:
+                        "Found a processing instruction without a closing '?>' in the OFX header"
+                            .to_string(),
+                });
+            };
+            rest = instruction[end_index + 2..].trim_start();
+            let (target, attributes) = instruction[..end_index]
+                .split_once(char::is_whitespace)
+                .unwrap_or((&instruction[..end_index], ""));
+            match target {
+                "xml" => {
+                    for (key, value) in parse_attributes(attributes)? {
+                        if key == "encoding" {
+                            s_encoding = Some(value.to_string());
+                        }
+                    }
+                }
+                "OFX" => {
+                    for (key, value) in parse_attributes(attributes)? {
+                        match key {
+                            "OFXHEADER" => {
+                                s_ofx_header = Some(parse_header_number("OFXHEADER", value)?)
+                            }
+                            "VERSION" => s_version = Some(parse_header_number("VERSION", value)?),
+                            "SECURITY" => s_security = Some(value.to_string()),
+                            "OLDFILEUID" => s_old_file_uid = none_if_unset(value),
+                            "NEWFILEUID" => s_new_file_uid = none_if_unset(value),
+                            // Ignored for the same reason as unknown keys in an SGML header
+                            _ => {}
+                        }
+                    }
+                }
+                // Any other processing instructions are not meant for us
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            ofx_header: s_ofx_header.ok_or(QFXParsingError::InvalidHeader {
+                message: "OFXHEADER is a required value in the OFX header".to_string(),
+            })?,
+            data: None,
+            version: s_version.ok_or(QFXParsingError::InvalidHeader {
+                message: "VERSION is a required value in the OFX header".to_string(),
+            })?,
+            security: s_security,
+            encoding: s_encoding,
+            charset: None,
+            compression: None,
+            old_file_uid: s_old_file_uid,
+            new_file_uid: s_new_file_uid,
+            flavour: OfxFlavour::Xml,
+        })
+    }
+
+    // Rejects the header values that describe a file this parser cannot read.
+    fn validate(&self) -> Result<(), QFXParsingError> {
+        if self.ofx_header != 100 && self.ofx_header != 200 {
+            return Err(QFXParsingError::UnsupportedHeaderValue {
+                message: format!(
+                    "OFXHEADER:{} is not supported, Expecting: 100 or 200",
+                    self.ofx_header
+                ),
+            });
+        }
+        if let Some(data) = self.data.as_deref().filter(|x| *x != "OFXSGML") {
+            return Err(QFXParsingError::UnsupportedHeaderValue {
+                message: format!("DATA:{} is not supported, Expecting: OFXSGML", data),
+            });
+        }
+        if let Some(security) = self.security.as_deref().filter(|x| *x != "NONE") {
+            // TYPE1 means the contents are encrypted at the application level
+            return Err(QFXParsingError::UnsupportedHeaderValue {
+                message: format!(
+                    "SECURITY:{} is not supported, only SECURITY:NONE files can be read",
+                    security
+                ),
+            });
+        }
+        if let Some(compression) = self.compression.as_deref().filter(|x| *x != "NONE") {
+            return Err(QFXParsingError::UnsupportedHeaderValue {
+                message: format!(
+                    "COMPRESSION:{} is not supported, only uncompressed files can be read",
+                    compression
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn parse_header_number(key: &str, value: &str) -> Result<u16, QFXParsingError> {
+    value
+        .parse::<u16>()
+        .map_err(|_| QFXParsingError::InvalidHeader {
+            message: format!("Expected a number for {}, Found {}", key, value),
+        })
+}
+
+// Parses the KEY="VALUE" pairs of a processing instruction. Eg. OFXHEADER="200" VERSION="211"
+fn parse_attributes(input: &str) -> Result<Vec<(&str, &str)>, QFXParsingError> {
+    let mut attributes = vec![];
+    let mut rest = input.trim();
+    while !rest.is_empty() {
+        let invalid = || QFXParsingError::InvalidHeader {
+            message: format!(
+                "Expected a KEY=\"VALUE\" pair in the OFX header, Found {}",
+                input.trim()
+            ),
+        };
+        let (key, value) = rest.split_once('=').ok_or_else(invalid)?;
+        let value = value.trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|x| *x == '"' || *x == '\'')
+            .ok_or_else(invalid)?;
+        let end_index = value[1..].find(quote).ok_or_else(invalid)? + 1;
+        attributes.push((key.trim(), &value[1..end_index]));
+        rest = value[end_index + 1..].trim_start();
+    }
+    Ok(attributes)
+}
+
+// OFX uses the literal NONE for unset values in the header
+fn none_if_unset(value: &str) -> Option<String> {
+    match value {
+        "NONE" | "" => None,
+        _ => Some(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod header_tests {
+    use super::*;
+
+    #[test]
+    fn test_header_parse_valid() {
+        let input = "\
+            OFXHEADER:100\n\
+            DATA:OFXSGML\n\
+            VERSION:102\n\
+            SECURITY:NONE\n\
+            ENCODING:USASCII\n\
+            CHARSET:1252\n\
+            COMPRESSION:NONE\n\
+            OLDFILEUID:NONE\n\
+            NEWFILEUID:20250730001\n\
+            \n";
+        let header = OfxHeader::parse(input).unwrap();
+        assert_eq!(header.flavour(), OfxFlavour::Sgml);
+        assert_eq!(header.ofx_header, 100);
+        assert_eq!(header.data.as_deref(), Some("OFXSGML"));
+        assert_eq!(header.version, 102);
+        assert_eq!(header.security.as_deref(), Some("NONE"));
+        assert_eq!(header.encoding.as_deref(), Some("USASCII"));
+        assert_eq!(header.charset.as_deref(), Some("1252"));
+        assert_eq!(header.compression.as_deref(), Some("NONE"));
+        assert!(header.old_file_uid.is_none());
+        assert_eq!(header.new_file_uid.as_deref(), Some("20250730001"));
+    }
+
+    #[test]
+    fn test_header_parse_minimal() {
+        let header = OfxHeader::parse("OFXHEADER:100\r\nVERSION:151\r\n").unwrap();
+        assert_eq!(header.version, 151);
+        assert!(header.data.is_none());
+        assert!(header.new_file_uid.is_none());
+    }
+
+    #[test]
+    fn test_header_parse_missing_version() {
+        let result = OfxHeader::parse("OFXHEADER:100\nDATA:OFXSGML\n");
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::InvalidHeader { message: msg, .. }) if msg.contains("VERSION is a required value")
+        ));
+    }
+
+    #[test]
+    fn test_header_parse_unknown_key() {
+        let header =
+            OfxHeader::parse("OFXHEADER:100\nX-BANKID:1234\nVERSION:102\nNEWFILEUID:ABC\n")
+                .unwrap();
+        assert_eq!(header.version, 102);
+        assert_eq!(header.new_file_uid.as_deref(), Some("ABC"));
+    }
+
+    #[test]
+    fn test_header_parse_unknown_key_missing_version() {
+        let result = OfxHeader::parse("OFXHEADER:100\nX-BANKID:1234\n");
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::InvalidHeader { message: msg, .. }) if msg.contains("VERSION is a required value")
+        ));
+    }
+
+    #[test]
+    fn test_header_flavour_from_syntax() {
+        let header = OfxHeader::parse("OFXHEADER:200\nVERSION:200\n").unwrap();
+        assert_eq!(header.flavour(), OfxFlavour::Sgml);
+        let header = OfxHeader::parse("<?OFX OFXHEADER=\"100\" VERSION=\"102\"?>").unwrap();
+        assert_eq!(header.flavour(), OfxFlavour::Xml);
+    }
+
+    #[test]
+    fn test_header_parse_malformed_line() {
+        let result = OfxHeader::parse("OFXHEADER:100\nVERSION 102\n");
+        assert!(matches!(result, Err(QFXParsingError::InvalidHeader { .. })));
+    }
+
+    #[test]
+    fn test_header_parse_non_numeric_version() {
+        let result = OfxHeader::parse("OFXHEADER:100\nVERSION:ONE\n");
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::InvalidHeader { message: msg, .. }) if msg.contains("VERSION")
+        ));
+    }
+
+    #[test]
+    fn test_header_parse_unsupported_security() {
+        let result = OfxHeader::parse("OFXHEADER:100\nVERSION:102\nSECURITY:TYPE1\n");
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::UnsupportedHeaderValue { message: msg, .. }) if msg.contains("TYPE1")
+        ));
+    }
+
+    #[test]
+    fn test_header_parse_xml() {
+        let input = "\
+            <?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n\
+            <?OFX OFXHEADER=\"200\" VERSION=\"211\" SECURITY=\"NONE\" OLDFILEUID=\"NONE\" NEWFILEUID=\"ABC-123\"?>\n";
+        let header = OfxHeader::parse(input).unwrap();
+        assert_eq!(header.flavour(), OfxFlavour::Xml);
+        assert_eq!(header.ofx_header, 200);
+        assert_eq!(header.version, 211);
+        assert_eq!(header.encoding.as_deref(), Some("UTF-8"));
+        assert_eq!(header.security.as_deref(), Some("NONE"));
+        assert!(header.old_file_uid.is_none());
+        assert_eq!(header.new_file_uid.as_deref(), Some("ABC-123"));
+        assert!(header.data.is_none());
+    }
+
+    #[test]
+    fn test_header_parse_xml_missing_version() {
+        let result = OfxHeader::parse("<?OFX OFXHEADER=\"200\"?>");
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::InvalidHeader { message: msg, .. }) if msg.contains("VERSION is a required value")
+        ));
+    }
+
+    #[test]
+    fn test_header_parse_xml_unknown_attribute() {
+        let header =
+            OfxHeader::parse("<?OFX OFXHEADER=\"200\" VERSION=\"211\" BANKID=\"1234\"?>").unwrap();
+        assert_eq!(header.version, 211);
+    }
+
+    #[test]
+    fn test_header_parse_xml_unterminated() {
+        let result = OfxHeader::parse("<?OFX OFXHEADER=\"200\" VERSION=\"211\"");
+        assert!(matches!(result, Err(QFXParsingError::InvalidHeader { .. })));
+    }
+
+    #[test]
+    fn test_header_parse_xml_unsupported_security() {
+        let result =
+            OfxHeader::parse("<?OFX OFXHEADER=\"200\" VERSION=\"211\" SECURITY=\"TYPE1\"?>");
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::UnsupportedHeaderValue { message: msg, .. }) if msg.contains("TYPE1")
+        ));
+    }
+
+    #[test]
+    fn test_header_parse_unsupported_compression() {
+        let result = OfxHeader::parse("OFXHEADER:100\nVERSION:102\nCOMPRESSION:GZIP\n");
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::UnsupportedHeaderValue { message: msg, .. }) if msg.contains("COMPRESSION")
+        ));
+    }
+}
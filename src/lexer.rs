@@ -0,0 +1,822 @@
+use crate::ErrorContext;
+use crate::OfxDateTime;
+use crate::OfxFlavour;
+use crate::ParseOptions;
+use crate::ParseWarning;
+use crate::QFXParsingError;
+use crate::datetime::parse_ofx_datetime;
+use crate::decode_entities;
+use std::fmt::Display;
+
+/// A position in the source document. Lines and columns start at 1 and columns count
+/// characters, not bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind<'a> {
+    // <NAME>
+    StartTag(&'a str),
+    // </NAME>
+    EndTag(&'a str),
+    // The trimmed text between two tags. Character entities have not been decoded yet.
+    Text(&'a str),
+    // <?OFX OFXHEADER="200"?>, without the <? and ?>
+    ProcessingInstruction(&'a str),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Token<'a> {
+    pub kind: TokenKind<'a>,
+    pub position: Position,
+}
+
+impl Display for TokenKind<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::StartTag(name) => write!(f, "{}", name),
+            TokenKind::EndTag(name) => write!(f, "/{}", name),
+            TokenKind::Text(text) => write!(f, "{}", text),
+            TokenKind::ProcessingInstruction(instruction) => write!(f, "?{}?", instruction),
+        }
+    }
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+// Splits a document in to tokens while keeping track of where each token started. Comments and
+// declarations such as <!DOCTYPE> are skipped. Brackets that do not belong to a tag are
+// rejected instead of being treated as the start or end of one, except for a '>' in the text
+// of an XML document where it is allowed. CDATA sections in XML documents are part of the text
+// they appear in.
+#[derive(Clone)]
+pub(crate) struct Lexer<'a> {
+    input: &'a str,
+    flavour: OfxFlavour,
+    position: Position,
+    // The end tag of a self closing element (Eg. <MEMO/>) that is still to be returned
+    pending: Option<Token<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            flavour: OfxFlavour::Sgml,
+            position: Position {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+            pending: None,
+        }
+    }
+
+    // Starts lexing a document of the given flavour at the given byte offset. Positions are
+    // still relative to the whole input so they line up with the file the user has open.
+    pub(crate) fn new_at(input: &'a str, offset: usize, flavour: OfxFlavour) -> Self {
+        let mut lexer = Self {
+            flavour,
+            ..Self::new(input)
+        };
+        lexer.advance(offset);
+        lexer
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position.offset..]
+    }
+
+    // Moves forward by the given number of bytes
+    fn advance(&mut self, length: usize) {
+        for character in self.rest()[..length].chars() {
+            if character == '\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else {
+                self.position.column += 1;
+            }
+        }
+        self.position.offset += length;
+    }
+
+    // The position the lexer has reached. At the end of the input this is just past the last
+    // character.
+    pub(crate) fn position(&self) -> Position {
+        self.position
+    }
+
+    fn syntax_error(&self, position: Position, message: &str) -> QFXParsingError {
+        QFXParsingError::InvalidSyntax {
+            message: message.to_string(),
+            context: Box::new(ErrorContext {
+                position: Some(position),
+                path: String::new(),
+            }),
+        }
+    }
+
+    // Lexes the markup starting with a '<' at the current position. Returns None for markup
+    // that is skipped.
+    fn lex_markup(&mut self) -> Result<Option<Token<'a>>, QFXParsingError> {
+        let position = self.position;
+        let rest = self.rest();
+        if rest.starts_with("<!--") {
+            let end_index = rest.find("-->").ok_or_else(|| {
+                self.syntax_error(position, "Found a comment without a closing '-->'")
+            })?;
+            self.advance(end_index + 3);
+            return Ok(None);
+        }
+        if let Some(instruction) = rest.strip_prefix("<?") {
+            let end_index = instruction.find("?>").ok_or_else(|| {
+                self.syntax_error(
+                    position,
+                    "Found a processing instruction without a closing '?>'",
+                )
+            })?;
+            self.advance(end_index + 4);
+            return Ok(Some(Token {
+                kind: TokenKind::ProcessingInstruction(instruction[..end_index].trim()),
+                position,
+            }));
+        }
+
+        let end_index = rest[1..]
+            .find(['<', '>'])
+            .filter(|x| rest[1 + x..].starts_with('>'))
+            .ok_or_else(|| self.syntax_error(position, "Found a '<' that does not start a tag"))?
+            + 1;
+        let contents = &rest[1..end_index];
+        self.advance(end_index + 1);
+        if contents.starts_with('!') {
+            // Declarations such as <!DOCTYPE ...> do not matter to us
+            return Ok(None);
+        }
+
+        let (kind, name) = if let Some(name) = contents.strip_prefix('/') {
+            (TokenKind::EndTag(name), name)
+        } else if let Some(name) = contents.strip_suffix('/') {
+            self.pending = Some(Token {
+                kind: TokenKind::EndTag(name),
+                position,
+            });
+            (TokenKind::StartTag(name), name)
+        } else {
+            (TokenKind::StartTag(contents), contents)
+        };
+        let is_valid_name = name
+            .chars()
+            .all(|x| x.is_ascii_alphanumeric() || matches!(x, '.' | '_' | '-' | ':'));
+        if name.is_empty() || !is_valid_name {
+            self.pending = None;
+            return Err(
+                self.syntax_error(position, &format!("Found an invalid tag <{}>", contents))
+            );
+        }
+        Ok(Some(Token { kind, position }))
+    }
+
+    // The length of the text at the start of the input in an XML document. The text runs up
+    // to the next tag, taking in any CDATA sections on the way.
+    fn xml_text_length(&self, input: &str) -> Result<usize, QFXParsingError> {
+        let mut length = 0;
+        loop {
+            let rest = &input[length..];
+            match rest.find('<') {
+                Some(index) if rest[index..].starts_with(CDATA_START) => {
+                    let end_index = rest[index..].find(CDATA_END).ok_or_else(|| {
+                        let mut start = self.clone();
+                        start.advance(input.len() - rest.len() + index);
+                        start.syntax_error(
+                            start.position,
+                            "Found a CDATA section without a closing ']]>'",
+                        )
+                    })?;
+                    length += index + end_index + CDATA_END.len();
+                }
+                Some(index) => return Ok(length + index),
+                None => return Ok(input.len()),
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, QFXParsingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(token) = self.pending.take() {
+            return Some(Ok(token));
+        }
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.advance(rest.len() - trimmed.len());
+            if trimmed.is_empty() {
+                return None;
+            }
+
+            let is_cdata = self.flavour == OfxFlavour::Xml && trimmed.starts_with(CDATA_START);
+            if trimmed.starts_with('<') && !is_cdata {
+                match self.lex_markup() {
+                    Ok(Some(token)) => return Some(Ok(token)),
+                    Ok(None) => continue,
+                    Err(e) => {
+                        // Nothing sensible can be lexed after a syntax error
+                        self.position.offset = self.input.len();
+                        return Some(Err(e));
+                    }
+                }
+            }
+
+            let position = self.position;
+            let text = match self.flavour {
+                OfxFlavour::Sgml => &trimmed[..trimmed.find('<').unwrap_or(trimmed.len())],
+                OfxFlavour::Xml => match self.xml_text_length(trimmed) {
+                    Ok(length) => &trimmed[..length],
+                    Err(e) => {
+                        self.position.offset = self.input.len();
+                        return Some(Err(e));
+                    }
+                },
+            };
+            if let Some(index) = text.find('>').filter(|_| self.flavour == OfxFlavour::Sgml) {
+                self.advance(index);
+                let error = self.syntax_error(self.position, "Found a '>' that does not end a tag");
+                self.position.offset = self.input.len();
+                return Some(Err(error));
+            }
+            self.advance(text.len());
+            return Some(Ok(Token {
+                kind: TokenKind::Text(text.trim_end()),
+                position,
+            }));
+        }
+    }
+}
+
+const CDATA_START: &str = "<![CDATA[";
+const CDATA_END: &str = "]]>";
+
+// Decodes the text of a leaf. The contents of CDATA sections are kept as they are and the
+// entities in the rest of the text are decoded.
+fn decode_text(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(CDATA_START) {
+        decoded.push_str(&decode_entities(&rest[..start]));
+        let contents = &rest[start + CDATA_START.len()..];
+        let end = contents.find(CDATA_END).unwrap_or(contents.len());
+        decoded.push_str(&contents[..end]);
+        rest = contents.get(end + CDATA_END.len()..).unwrap_or("");
+    }
+    decoded.push_str(&decode_entities(rest));
+    decoded
+}
+
+// Aggregates that can appear more than once in their parent. These are indexed in the path
+// of an error so the right one can be found.
+const REPEATED_AGGREGATES: [&str; 4] = ["STMTTRN", "STMTTRNRS", "CCSTMTTRNRS", "BAL"];
+
+// An element that has been started but not yet ended
+struct Element<'a> {
+    name: &'a str,
+    index: Option<usize>,
+    // How many times each repeated aggregate has been seen inside this element
+    children: Vec<(&'a str, usize)>,
+}
+
+// The tokens of a document as they are seen by the Parseable implementations. On top of the
+// lexer this skips processing instructions and takes care of the end tags of leaf elements,
+// which are optional in OFX 1.x and always present in OFX 2.x. It also keeps track of the
+// elements that are open so errors can say where they happened.
+pub(crate) struct TokenStream<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<Token<'a>>,
+    path: Vec<Element<'a>>,
+    // The last element in the path has ended. It is kept until the next token is read so that
+    // errors about the element (Eg. a missing required value) still point at it.
+    ended: bool,
+    // The position of the last token that was read
+    position: Option<Position>,
+    // Whether unknown tags are errors. When false they are skipped and a warning is recorded
+    strict: bool,
+    // Whether a STATUS with an ERROR severity is returned as an error
+    server_errors: bool,
+    warnings: Vec<ParseWarning>,
+}
+
+impl<'a> TokenStream<'a> {
+    pub(crate) fn new(lexer: Lexer<'a>, options: &ParseOptions) -> Self {
+        Self {
+            lexer,
+            peeked: None,
+            path: vec![],
+            ended: false,
+            position: None,
+            strict: options.strict,
+            server_errors: options.server_errors,
+            warnings: vec![],
+        }
+    }
+
+    pub(crate) fn server_errors(&self) -> bool {
+        self.server_errors
+    }
+
+    // The warnings for everything that was skipped so far
+    pub(crate) fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    // Reads the next token without updating the path
+    fn read(&mut self) -> Result<Option<Token<'a>>, QFXParsingError> {
+        if let Some(token) = self.peeked.take() {
+            return Ok(Some(token));
+        }
+        for token in self.lexer.by_ref() {
+            let token = token?;
+            if !matches!(token.kind, TokenKind::ProcessingInstruction(_)) {
+                return Ok(Some(token));
+            }
+        }
+        Ok(None)
+    }
+
+    // Returns the next start tag, end tag or text token. None is returned at the end of the input.
+    pub(crate) fn next_token(&mut self) -> Result<Option<Token<'a>>, QFXParsingError> {
+        if self.ended {
+            self.path.pop();
+            self.ended = false;
+        }
+        let Some(token) = self.read()? else {
+            self.position = Some(self.lexer.position());
+            return Ok(None);
+        };
+        self.position = Some(token.position);
+        match token.kind {
+            TokenKind::StartTag(name) => {
+                let index = self.path.last_mut().and_then(|parent| {
+                    if !REPEATED_AGGREGATES.contains(&name) {
+                        return None;
+                    }
+                    match parent.children.iter_mut().find(|(x, _)| *x == name) {
+                        Some((_, count)) => {
+                            *count += 1;
+                            Some(*count - 1)
+                        }
+                        None => {
+                            parent.children.push((name, 1));
+                            Some(0)
+                        }
+                    }
+                });
+                self.path.push(Element {
+                    name,
+                    index,
+                    children: vec![],
+                });
+            }
+            TokenKind::EndTag(name) => {
+                self.ended = self.path.last().is_some_and(|x| x.name == name);
+            }
+            _ => {}
+        }
+        Ok(Some(token))
+    }
+
+    // Reads the value of the leaf element whose start tag was just read. Eg. for <NAME>Foo</NAME>
+    // this is called after the NAME token and returns Foo. The end tag is consumed if present.
+    pub(crate) fn value(&mut self, tag: &str) -> Result<String, QFXParsingError> {
+        // The leaf is taken off the path with the next token
+        self.ended = self.path.last().is_some_and(|x| x.name == tag);
+        match self.read()? {
+            Some(Token {
+                kind: TokenKind::Text(text),
+                position,
+            }) => {
+                self.position = Some(position);
+                self.peeked = self.read()?;
+                if matches!(self.peeked, Some(Token { kind: TokenKind::EndTag(name), .. }) if name == tag)
+                {
+                    self.peeked = None;
+                }
+                Ok(decode_text(text))
+            }
+            // An empty element. Eg. <MEMO></MEMO>
+            Some(Token {
+                kind: TokenKind::EndTag(name),
+                ..
+            }) if name == tag => Ok(String::new()),
+            Some(token) => {
+                self.position = Some(token.position);
+                Err(QFXParsingError::UnexpectedToken {
+                    message: format!(
+                        "Expected a value following the {} token, Found {}",
+                        tag, token
+                    ),
+                    expected: Some(format!("a value for {}", tag)),
+                    found: Some(token.to_string()),
+                    context: Box::default(),
+                })
+            }
+            None => {
+                self.position = Some(self.lexer.position());
+                Err(QFXParsingError::unexpected_eof(
+                    format!("Expected token following the {} token", tag),
+                    &format!("a value for {}", tag),
+                ))
+            }
+        }
+    }
+
+    // Reads the value of a datetime leaf like DTPOSTED and parses it. Every OFX datetime goes
+    // through here so they all accept the same formats and report errors the same way.
+    pub(crate) fn datetime(&mut self, tag: &str) -> Result<OfxDateTime, QFXParsingError> {
+        let value = self.value(tag)?;
+        parse_ofx_datetime(&value).map_err(|e| {
+            QFXParsingError::invalid_date(
+                format!("Failed to parse datetime for {} with {}", tag, e),
+                &value,
+            )
+        })
+    }
+
+    // Handles a token that the aggregate being parsed does not know about. In strict mode the
+    // given error is returned. Otherwise the token is skipped along with its value or, for an
+    // unknown aggregate, everything up to its matching end tag.
+    pub(crate) fn skip_unknown(
+        &mut self,
+        token: Token<'a>,
+        error: QFXParsingError,
+    ) -> Result<(), QFXParsingError> {
+        if self.strict {
+            return Err(error);
+        }
+        self.warnings.push(ParseWarning {
+            message: error.message().to_string(),
+            tag: token.to_string(),
+            context: self.context(),
+            skipped: true,
+        });
+
+        let TokenKind::StartTag(name) = token.kind else {
+            // Stray text and end tags do not have anything else to skip
+            return Ok(());
+        };
+        match self.read()? {
+            Some(Token {
+                kind: TokenKind::Text(_),
+                ..
+            }) => {
+                // A leaf. Eg. <INTU.XYZ>Foo</INTU.XYZ>
+                self.peeked = self.read()?;
+                if matches!(self.peeked, Some(Token { kind: TokenKind::EndTag(x), .. }) if x == name)
+                {
+                    self.peeked = None;
+                }
+            }
+            // An empty leaf. Eg. <INTU.XYZ></INTU.XYZ>
+            Some(Token {
+                kind: TokenKind::EndTag(x),
+                ..
+            }) if x == name => {}
+            // A leaf without a value. The end tag belongs to the enclosing aggregate
+            Some(
+                next @ Token {
+                    kind: TokenKind::EndTag(_),
+                    ..
+                },
+            ) => self.peeked = Some(next),
+            // A leaf without a value followed by the next element. Eg. <INTU.FLAG><NAME>Foo
+            Some(next) if !self.closes_before_parent(name, next) => self.peeked = Some(next),
+            Some(mut next) => {
+                // An aggregate. Only elements with the same name are counted since the leaves
+                // inside it may not have end tags.
+                let mut depth = 1;
+                loop {
+                    match next.kind {
+                        TokenKind::StartTag(x) if x == name => depth += 1,
+                        TokenKind::EndTag(x) if x == name => depth -= 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                    next = self.read()?.ok_or_else(|| {
+                        self.position = Some(self.lexer.position());
+                        QFXParsingError::unexpected_eof(
+                            format!(
+                                "Found unexpected EOF. Was still expecting the '/{}' token",
+                                name
+                            ),
+                            &format!("/{}", name),
+                        )
+                    })?;
+                }
+            }
+            None => {
+                self.position = Some(self.lexer.position());
+                return Err(QFXParsingError::unexpected_eof(
+                    format!("Expected token following the {} token", name),
+                    &format!("a value for {}", name),
+                ));
+            }
+        }
+        // The unknown element is taken off the path with the next token
+        self.ended = true;
+        Ok(())
+    }
+
+    // Looks ahead for the end tag of the unknown element that was just started, beginning with
+    // the given token. In SGML files a start tag followed by another start tag is either an
+    // aggregate or a leaf without a value, and only the aggregate is closed before its parent.
+    fn closes_before_parent(&self, name: &str, first: Token<'a>) -> bool {
+        let parent = self.path.len().checked_sub(2).map(|x| self.path[x].name);
+        let mut lexer = self.lexer.clone();
+        let mut depth = 1;
+        let mut next = Some(Ok(first));
+        while let Some(Ok(token)) = next {
+            match token.kind {
+                TokenKind::StartTag(x) if x == name => depth += 1,
+                TokenKind::EndTag(x) if x == name => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return true;
+                    }
+                }
+                TokenKind::EndTag(x) if Some(x) == parent => return false,
+                _ => {}
+            }
+            next = lexer.next();
+        }
+        false
+    }
+
+    // Records a warning for a value that is kept even though it is not valid, such as a
+    // transaction type that is not in the spec. The document is usable either way, so this
+    // never fails and the warning is only recorded when unknown tags are being skipped.
+    pub(crate) fn keep_invalid_value(&mut self, tag: &str, message: String) {
+        if self.strict {
+            return;
+        }
+        self.warnings.push(ParseWarning {
+            message,
+            tag: tag.to_string(),
+            context: self.context(),
+            skipped: false,
+        });
+    }
+
+    // Describes where the stream is. Used to fill in the context of errors.
+    pub(crate) fn context(&self) -> ErrorContext {
+        let path: Vec<String> = self
+            .path
+            .iter()
+            .map(|x| match x.index {
+                Some(index) => format!("{}[{}]", x.name, index),
+                None => x.name.to_string(),
+            })
+            .collect();
+        ErrorContext {
+            position: self.position,
+            path: path.join("/"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod lexer_tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<TokenKind<'_>> {
+        Lexer::new(input).map(|x| x.unwrap().kind).collect()
+    }
+
+    #[test]
+    fn test_lexer_sgml() {
+        let input = "<STMTTRN>\n  <TRNTYPE>DEBIT\n  <NAME>AT&amp;T  \n</STMTTRN>";
+        assert_eq!(
+            kinds(input),
+            vec![
+                TokenKind::StartTag("STMTTRN"),
+                TokenKind::StartTag("TRNTYPE"),
+                TokenKind::Text("DEBIT"),
+                TokenKind::StartTag("NAME"),
+                TokenKind::Text("AT&amp;T"),
+                TokenKind::EndTag("STMTTRN"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_xml() {
+        let input =
+            "<?xml version=\"1.0\"?>\n<!-- a > comment -->\n<OFX><MEMO/><NAME>Foo</NAME></OFX>";
+        assert_eq!(
+            kinds(input),
+            vec![
+                TokenKind::ProcessingInstruction("xml version=\"1.0\""),
+                TokenKind::StartTag("OFX"),
+                TokenKind::StartTag("MEMO"),
+                TokenKind::EndTag("MEMO"),
+                TokenKind::StartTag("NAME"),
+                TokenKind::Text("Foo"),
+                TokenKind::EndTag("NAME"),
+                TokenKind::EndTag("OFX"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_positions() {
+        let input = "<OFX>\n  <NAME>Café\n  </OFX>";
+        let positions: Vec<Position> = Lexer::new(input).map(|x| x.unwrap().position).collect();
+        assert_eq!(
+            positions,
+            vec![
+                Position {
+                    offset: 0,
+                    line: 1,
+                    column: 1
+                },
+                Position {
+                    offset: 8,
+                    line: 2,
+                    column: 3
+                },
+                Position {
+                    offset: 14,
+                    line: 2,
+                    column: 9
+                },
+                Position {
+                    offset: 22,
+                    line: 3,
+                    column: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_new_at() {
+        let input = "OFXHEADER:100\n\n<OFX>";
+        let token = Lexer::new_at(input, 15, OfxFlavour::Sgml)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(token.kind, TokenKind::StartTag("OFX"));
+        assert_eq!(token.position.line, 3);
+        assert_eq!(token.position.column, 1);
+    }
+
+    #[test]
+    fn test_lexer_stray_less_than() {
+        let result: Result<Vec<Token>, QFXParsingError> =
+            Lexer::new("<NAME>B<3 CAFE\n<MEMO>Foo").collect();
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::InvalidSyntax { context, .. })
+                if context.position.is_some_and(|x| x.line == 1 && x.column == 8)
+        ));
+    }
+
+    #[test]
+    fn test_lexer_stray_greater_than() {
+        let result: Result<Vec<Token>, QFXParsingError> = Lexer::new("<NAME>A > B<MEMO>").collect();
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::InvalidSyntax { context, .. })
+                if context.position.is_some_and(|x| x.line == 1 && x.column == 9)
+        ));
+    }
+
+    #[test]
+    fn test_lexer_xml_text() {
+        let input = "<NAME>A > B</NAME><MEMO><![CDATA[<b> & </b>]]> C</MEMO>";
+        let kinds: Vec<TokenKind> = Lexer::new_at(input, 0, OfxFlavour::Xml)
+            .map(|x| x.unwrap().kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::StartTag("NAME"),
+                TokenKind::Text("A > B"),
+                TokenKind::EndTag("NAME"),
+                TokenKind::StartTag("MEMO"),
+                TokenKind::Text("<![CDATA[<b> & </b>]]> C"),
+                TokenKind::EndTag("MEMO"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_xml_unterminated_cdata() {
+        let result: Result<Vec<Token>, QFXParsingError> =
+            Lexer::new_at("<NAME>A\n<![CDATA[B</NAME>", 0, OfxFlavour::Xml).collect();
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::InvalidSyntax { context, .. })
+                if context.position.is_some_and(|x| x.line == 2 && x.column == 1)
+        ));
+    }
+
+    #[test]
+    fn test_lexer_invalid_tag_name() {
+        let result: Result<Vec<Token>, QFXParsingError> = Lexer::new("<NAME>A <B C> D").collect();
+        assert!(
+            matches!(result, Err(QFXParsingError::InvalidSyntax { message: msg, .. }) if msg.contains("<B C>"))
+        );
+    }
+
+    #[test]
+    fn test_lexer_unterminated_tag() {
+        let result: Result<Vec<Token>, QFXParsingError> = Lexer::new("<OFX><NAME").collect();
+        assert!(matches!(result, Err(QFXParsingError::InvalidSyntax { .. })));
+    }
+}
+
+#[cfg(test)]
+mod token_stream_tests {
+    use super::*;
+
+    #[test]
+    fn test_token_stream_value_with_end_tag() {
+        let mut tokens = TokenStream::new(
+            Lexer::new("<NAME>Foo</NAME></STMTTRN>"),
+            &ParseOptions::default(),
+        );
+        tokens.next_token().unwrap();
+        assert_eq!(tokens.value("NAME").unwrap(), "Foo");
+        let token = tokens.next_token().unwrap().unwrap();
+        assert_eq!(token.kind, TokenKind::EndTag("STMTTRN"));
+    }
+
+    #[test]
+    fn test_token_stream_value_without_end_tag() {
+        let mut tokens =
+            TokenStream::new(Lexer::new("<NAME>AT&amp;T<MEMO>"), &ParseOptions::default());
+        tokens.next_token().unwrap();
+        assert_eq!(tokens.value("NAME").unwrap(), "AT&T");
+        let token = tokens.next_token().unwrap().unwrap();
+        assert_eq!(token.kind, TokenKind::StartTag("MEMO"));
+    }
+
+    #[test]
+    fn test_token_stream_value_with_cdata() {
+        let input = "<NAME>AT&amp;T <![CDATA[A &amp; B]]></NAME>";
+        let mut tokens = TokenStream::new(
+            Lexer::new_at(input, 0, OfxFlavour::Xml),
+            &ParseOptions::default(),
+        );
+        tokens.next_token().unwrap();
+        assert_eq!(tokens.value("NAME").unwrap(), "AT&T A &amp; B");
+    }
+
+    #[test]
+    fn test_token_stream_value_keeps_mismatched_end_tag() {
+        let mut tokens = TokenStream::new(Lexer::new("<NAME>Foo</MEMO>"), &ParseOptions::default());
+        tokens.next_token().unwrap();
+        assert_eq!(tokens.value("NAME").unwrap(), "Foo");
+        let token = tokens.next_token().unwrap().unwrap();
+        assert_eq!(token.kind, TokenKind::EndTag("MEMO"));
+    }
+
+    #[test]
+    fn test_token_stream_value_empty_element() {
+        let mut tokens = TokenStream::new(Lexer::new("<MEMO></MEMO>"), &ParseOptions::default());
+        tokens.next_token().unwrap();
+        assert_eq!(tokens.value("MEMO").unwrap(), "");
+    }
+
+    #[test]
+    fn test_token_stream_value_followed_by_tag() {
+        let mut tokens = TokenStream::new(Lexer::new("<MEMO><NAME>Foo"), &ParseOptions::default());
+        tokens.next_token().unwrap();
+        assert!(matches!(
+            tokens.value("MEMO"),
+            Err(QFXParsingError::UnexpectedToken { message: msg, .. }) if msg.contains("MEMO") && msg.contains("NAME")
+        ));
+    }
+
+    #[test]
+    fn test_token_stream_skips_processing_instructions() {
+        let mut tokens = TokenStream::new(
+            Lexer::new("<?OFX OFXHEADER=\"200\"?><OFX>"),
+            &ParseOptions::default(),
+        );
+        let token = tokens.next_token().unwrap().unwrap();
+        assert_eq!(token.kind, TokenKind::StartTag("OFX"));
+        assert!(tokens.next_token().unwrap().is_none());
+    }
+}
@@ -1,45 +1,314 @@
+mod account_type;
+mod amount;
+mod balance;
+mod balance_type;
 mod bank_msg;
 mod credit_card;
+mod datetime;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+mod encoding;
+mod header;
+mod lexer;
 mod sign_on;
+mod status_code;
+mod transaction_type;
 
 use bank_msg::BankMsgSrsV1;
-use chrono::DateTime;
-use chrono::NaiveDateTime;
-use chrono::Utc;
-use chrono::format::ParseError;
+use bank_msg::Bankacctfrom;
 use credit_card::CCMsgSrsV1;
+use credit_card::Ccacctfrom;
+use lexer::Lexer;
+use lexer::TokenKind;
+use lexer::TokenStream;
 use sign_on::SignOnMsgSrsV1;
+use std::borrow::Cow;
 use std::fmt::Display;
 use std::fs::File;
 use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
 use thiserror::Error;
 
+pub use account_type::AccountType;
+pub use amount::Amount;
+pub use amount::ParseAmountError;
+pub use balance::Bal;
+pub use balance::BalList;
+pub use balance_type::BalanceType;
+pub use datetime::DateTimePrecision;
+pub use datetime::OfxDateTime;
+pub use datetime::ParseDateTimeError;
+pub use encoding::Encoding;
+pub use header::OfxFlavour;
+pub use header::OfxHeader;
+pub use lexer::Position;
+pub use status_code::Severity;
+pub use status_code::StatusCode;
+pub use transaction_type::TransactionType;
+
 #[derive(Error, Debug)]
 pub enum QFXParsingError {
-    #[error("Unexpected token found")]
-    UnexpectedToken(String),
-    #[error("Found unexpected end of file. Expecting more tokens")]
-    UnexpectedEOF(String),
-    #[error("Missing a required value in the QFX file")]
-    MissingRequiredValue(String),
-    #[error("Found an unexpected datetime format in the qfx file")]
-    UnexpectedDateFormat(String),
-    #[error("Missing a required value in the QFX file")]
-    InvalidTransactionAmount(String),
-    #[error("File not found")]
-    FileNotFound(String),
-    #[error("File read error")]
-    FileReadError(String),
+    #[error("Found an unexpected token. {message}{context}")]
+    UnexpectedToken {
+        message: String,
+        /// What the parser was expecting to find, if it was expecting something specific
+        expected: Option<String>,
+        /// The token that was found instead
+        found: Option<String>,
+        context: Box<ErrorContext>,
+    },
+    #[error("Found unexpected end of file. {message}{context}")]
+    UnexpectedEOF {
+        message: String,
+        /// The token that was still expected when the input ended
+        expected: String,
+        context: Box<ErrorContext>,
+    },
+    #[error("Missing a required value in the QFX file. {message}{context}")]
+    MissingRequiredValue {
+        message: String,
+        /// The tag that was missing. Eg. TRNAMT
+        field: String,
+        context: Box<ErrorContext>,
+    },
+    #[error("Found an unexpected datetime format in the QFX file. {message}{context}")]
+    UnexpectedDateFormat {
+        message: String,
+        value: String,
+        context: Box<ErrorContext>,
+    },
+    #[error("Found an invalid transaction amount in the QFX file. {message}{context}")]
+    InvalidTransactionAmount {
+        message: String,
+        value: String,
+        context: Box<ErrorContext>,
+    },
+    #[error("Found malformed markup in the QFX file. {message}{context}")]
+    InvalidSyntax {
+        message: String,
+        context: Box<ErrorContext>,
+    },
+    /// Only returned when `ParseOptions::server_errors` is set
+    #[error("The server responded with status {code}. {message}{context}")]
+    ServerError {
+        code: StatusCode,
+        /// The MESSAGE of the STATUS, or a description of the code if there was none
+        message: String,
+        context: Box<ErrorContext>,
+    },
+    #[error("File not found. {message}")]
+    FileNotFound { message: String },
+    #[error("File read error. {message}")]
+    FileReadError { message: String },
+    #[error("Found a malformed OFX header. {message}")]
+    InvalidHeader { message: String },
+    #[error("The OFX header contains a value that is not supported. {message}")]
+    UnsupportedHeaderValue { message: String },
+    #[error("Could not decode the file with its character encoding. {message}")]
+    InvalidEncoding { message: String },
+}
+
+/// Where in the document an error was found.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// The position of the token the error was found at, or the end of the input for
+    /// unexpected EOF errors.
+    pub position: Option<Position>,
+    /// The aggregates that were being parsed. Eg.
+    /// OFX/BANKMSGSRSV1/STMTTRNRS/STMTRS/BANKTRANLIST/STMTTRN[412]/TRNAMT. Aggregates that can
+    /// repeat are indexed from 0 in the order they appear in their parent.
+    pub path: String,
+}
+
+impl Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(position) = self.position {
+            write!(f, " (line {}, column {})", position.line, position.column)?;
+        }
+        if !self.path.is_empty() {
+            write!(f, " in {}", self.path)?;
+        }
+        Ok(())
+    }
+}
+
+impl QFXParsingError {
+    pub(crate) fn unexpected_token(message: String, found: impl Display) -> Self {
+        QFXParsingError::UnexpectedToken {
+            message,
+            expected: None,
+            found: Some(found.to_string()),
+            context: Box::default(),
+        }
+    }
+
+    // An unknown token in an aggregate. The tags the aggregate accepts and its end tag are
+    // what was expected instead.
+    pub(crate) fn unknown_token(
+        message: String,
+        found: impl Display,
+        tags: &[&str],
+        end_tag: &str,
+    ) -> Self {
+        QFXParsingError::UnexpectedToken {
+            message,
+            expected: Some(format!("one of {} or /{}", tags.join(", "), end_tag)),
+            found: Some(found.to_string()),
+            context: Box::default(),
+        }
+    }
+
+    pub(crate) fn unexpected_eof(message: String, expected: &str) -> Self {
+        QFXParsingError::UnexpectedEOF {
+            message,
+            expected: expected.to_string(),
+            context: Box::default(),
+        }
+    }
+
+    pub(crate) fn missing_value(message: String, field: &str) -> Self {
+        QFXParsingError::MissingRequiredValue {
+            message,
+            field: field.to_string(),
+            context: Box::default(),
+        }
+    }
+
+    pub(crate) fn invalid_date(message: String, value: &str) -> Self {
+        QFXParsingError::UnexpectedDateFormat {
+            message,
+            value: value.to_string(),
+            context: Box::default(),
+        }
+    }
+
+    pub(crate) fn invalid_amount(message: String, value: &str) -> Self {
+        QFXParsingError::InvalidTransactionAmount {
+            message,
+            value: value.to_string(),
+            context: Box::default(),
+        }
+    }
+
+    /// The message describing what went wrong, without the position information.
+    pub fn message(&self) -> &str {
+        match self {
+            QFXParsingError::UnexpectedToken { message, .. }
+            | QFXParsingError::UnexpectedEOF { message, .. }
+            | QFXParsingError::MissingRequiredValue { message, .. }
+            | QFXParsingError::UnexpectedDateFormat { message, .. }
+            | QFXParsingError::InvalidTransactionAmount { message, .. }
+            | QFXParsingError::InvalidSyntax { message, .. }
+            | QFXParsingError::ServerError { message, .. }
+            | QFXParsingError::FileNotFound { message }
+            | QFXParsingError::FileReadError { message }
+            | QFXParsingError::InvalidHeader { message }
+            | QFXParsingError::UnsupportedHeaderValue { message }
+            | QFXParsingError::InvalidEncoding { message } => message,
+        }
+    }
+
+    /// Where in the document the error was found. None for errors that are not about the
+    /// contents of the document, such as a missing file or a bad header.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            QFXParsingError::UnexpectedToken { context, .. }
+            | QFXParsingError::UnexpectedEOF { context, .. }
+            | QFXParsingError::MissingRequiredValue { context, .. }
+            | QFXParsingError::UnexpectedDateFormat { context, .. }
+            | QFXParsingError::InvalidTransactionAmount { context, .. }
+            | QFXParsingError::InvalidSyntax { context, .. }
+            | QFXParsingError::ServerError { context, .. } => Some(context.as_ref()),
+            _ => None,
+        }
+    }
+
+    // Fills in the parts of the context that were not known where the error was created.
+    pub(crate) fn with_context(mut self, found: ErrorContext) -> Self {
+        let context = match &mut self {
+            QFXParsingError::UnexpectedToken { context, .. }
+            | QFXParsingError::UnexpectedEOF { context, .. }
+            | QFXParsingError::MissingRequiredValue { context, .. }
+            | QFXParsingError::UnexpectedDateFormat { context, .. }
+            | QFXParsingError::InvalidTransactionAmount { context, .. }
+            | QFXParsingError::InvalidSyntax { context, .. }
+            | QFXParsingError::ServerError { context, .. } => context,
+            _ => return self,
+        };
+        if context.position.is_none() {
+            context.position = found.position;
+        }
+        if context.path.is_empty() {
+            context.path = found.path;
+        }
+        self
+    }
 }
 
 pub(crate) trait Parseable<'a> {
-    // Parsing function that takes in an iterator on some tokens. Consumes some tokens
-    // and increments the Iterator.
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError>
+    // The tags of the elements the aggregate can contain, not counting its end tag. Errors for
+    // unknown tags list them as what was expected instead.
+    const TAGS: &'static [&'static str];
+
+    // Parsing function that takes in a stream of tokens. Called after the start tag of the
+    // aggregate has been read and consumes the tokens up to and including its end tag.
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError>
     where
         Self: Sized;
 }
 
+/// Options that change how a QFX document is read.
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    /// Decode the file with this encoding, ignoring the byte order mark and the header.
+    pub encoding: Option<Encoding>,
+    /// Fail on tags the parser does not know about. When false, unknown leaves and whole
+    /// unknown aggregates are skipped and a `ParseWarning` is returned for each of them, as
+    /// well as for values that are kept even though they are not in the spec. Eg. a TRNTYPE of
+    /// ACH. Defaults to true.
+    pub strict: bool,
+    /// Return a `QFXParsingError::ServerError` for a STATUS with an ERROR severity instead of
+    /// the parsed document. Defaults to false.
+    pub server_errors: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            encoding: None,
+            strict: true,
+            server_errors: false,
+        }
+    }
+}
+
+/// Something that was skipped, or a value that was kept even though it is not valid, while
+/// parsing a document leniently.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseWarning {
+    pub message: String,
+    /// The tag that was skipped or that had the invalid value. End tags start with a '/'.
+    pub tag: String,
+    pub context: ErrorContext,
+    /// False when the tag was kept and only its value was not valid
+    pub skipped: bool,
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.skipped {
+            write!(f, "Skipped {}. {}{}", self.tag, self.message, self.context)
+        } else {
+            write!(
+                f,
+                "Kept the invalid value of {}. {}{}",
+                self.tag, self.message, self.context
+            )
+        }
+    }
+}
+
 #[derive(Clone)]
 /// NOTE: While there are definitely other types that are available in the OFX standard
 /// I have made a consious decision to only implement the tags used in practice. My research
@@ -47,6 +316,8 @@ pub(crate) trait Parseable<'a> {
 /// used commonly in QFX files. For now this is being left as a future work item that will
 /// be implemented should the need arise for it.
 pub struct QFX {
+    /// The header found before the `<OFX>` tag, if the file had one.
+    pub header: Option<OfxHeader>,
     pub sign_on_msg_srs_v1: Option<SignOnMsgSrsV1>,
     pub credit_card_msg_srs_v1: Option<CCMsgSrsV1>,
     pub bank_msg_srs_v1: Option<BankMsgSrsV1>,
@@ -54,56 +325,229 @@ pub struct QFX {
 
 #[derive(Clone)]
 pub struct Status {
-    pub code: String,
-    pub severity: String,
+    pub code: StatusCode,
+    pub severity: Severity,
     pub message: Option<String>,
 }
 
 #[derive(Clone)]
 pub struct LedgerBal {
-    pub balance_amount: String,
-    pub dt_as_of: DateTime<Utc>,
+    pub balance_amount: Amount,
+    pub dt_as_of: OfxDateTime,
 }
 
 #[derive(Clone)]
 pub struct AvailableBalance {
-    pub balance_amount: String,
-    pub dt_as_of: DateTime<Utc>,
+    pub balance_amount: Amount,
+    pub dt_as_of: OfxDateTime,
 }
 
 #[derive(Clone)]
 pub struct BankTranList {
-    pub dt_start: DateTime<Utc>,
-    pub dt_end: DateTime<Utc>,
+    pub dt_start: OfxDateTime,
+    pub dt_end: OfxDateTime,
     pub transactions: Vec<Stmttrn>,
 }
 
 #[derive(Clone, Debug)]
 pub struct Stmttrn {
-    pub trans_type: String,
-    pub dt_posted: DateTime<Utc>,
-    pub trans_amount: f64,
+    pub trans_type: TransactionType,
+    pub dt_posted: OfxDateTime,
+    pub trans_amount: Amount,
     pub fit_id: String,
     pub correct_fit_id: Option<String>,
     pub correct_action: Option<String>,
-    pub name: String,
+    /// Date the user initiated the transaction, if known
+    pub dt_user: Option<OfxDateTime>,
+    /// Date the funds are available
+    pub dt_avail: Option<OfxDateTime>,
+    /// Server assigned transaction id
+    pub srvr_tid: Option<String>,
+    /// Reference number that uniquely identifies the transaction
+    pub ref_num: Option<String>,
+    pub payee_id: Option<String>,
+    /// Standard Industrial Classification code
+    pub sic: Option<String>,
+    /// Either the NAME or the PAYEE is required. Use payee_name() to get whichever one is set
+    pub name: Option<String>,
+    pub payee: Option<Payee>,
+    /// Extended name of the payee
+    pub ext_name: Option<String>,
     pub memo: Option<String>,
     pub check_num: Option<String>, // Should only be used with CHECK or DEBIT transactions
+    /// The account the money went to in a transfer. At most one of the BANKACCTTO and
+    /// CCACCTTO is set
+    pub bankacctto: Option<Bankacctfrom>,
+    pub ccacctto: Option<Ccacctfrom>,
+    /// Set when TRNAMT is in a currency other than the CURDEF of the statement
+    pub currency: Option<Currency>,
+    /// Set when TRNAMT is in the CURDEF of the statement but the transaction was made in
+    /// another currency
+    pub orig_currency: Option<Currency>,
+}
+
+/// A CURRENCY or ORIGCURRENCY aggregate. `rate` is the number of CURDEF units one unit of
+/// `symbol` is worth.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Currency {
+    /// CURRATE
+    pub rate: Amount,
+    /// CURSYM. The ISO 4217 code of the currency. Eg. EUR
+    pub symbol: String,
+}
+
+/// The PAYEE aggregate of a transaction. Used instead of NAME when the financial institution
+/// sends the full details of who was paid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Payee {
+    pub name: String,
+    pub addr1: Option<String>,
+    pub addr2: Option<String>,
+    pub addr3: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+    pub phone: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Transaction {
-    pub trans_type: String,
-    pub dt_posted: DateTime<Utc>,
-    pub trans_amount: f64,
+    pub trans_type: TransactionType,
+    pub dt_posted: OfxDateTime,
+    pub trans_amount: Amount,
     pub fit_id: String,
     pub correct_fit_id: String,
+    pub dt_user: Option<OfxDateTime>,
+    pub dt_avail: Option<OfxDateTime>,
+    pub srvr_tid: String,
+    pub ref_num: String,
+    pub payee_id: String,
+    pub sic: String,
+    /// The NAME of the transaction, or the name in its PAYEE
     pub name: String,
+    pub payee: Option<Payee>,
+    pub ext_name: String,
     pub memo: String,
     pub check_num: String,
     pub account_id: String,
-    pub account_type: String,
+    pub account_type: AccountType,
+    pub bank_id: String,
+    /// The CURDEF of the statement
     pub currency: String,
+    /// The amount in the currency of the statement. None when it can not be worked out from
+    /// the CURRATE. Eg. a rate of zero
+    pub statement_amount: Option<Amount>,
+    /// The amount in the currency the transaction was made in. None when it can not be worked
+    /// out from the CURRATE
+    pub original_amount: Option<Amount>,
+    /// The currency the transaction was made in. The same as `currency` unless the
+    /// transaction has a CURRENCY or ORIGCURRENCY
+    pub original_currency: String,
+    /// CURRATE of the CURRENCY or ORIGCURRENCY, if the transaction has one
+    pub currency_rate: Option<Amount>,
+    /// The account on the other side of a transfer, from the BANKACCTTO or CCACCTTO
+    pub counterparty_account_id: String,
+    /// None when the transaction does not have a BANKACCTTO or CCACCTTO
+    pub counterparty_account_type: Option<AccountType>,
+    pub counterparty_bank_id: String,
+}
+
+impl Stmttrn {
+    /// The NAME of the transaction, or the name in its PAYEE when there is no NAME.
+    pub fn payee_name(&self) -> &str {
+        match (&self.name, &self.payee) {
+            (Some(name), _) => name,
+            (None, Some(payee)) => &payee.name,
+            (None, None) => "",
+        }
+    }
+
+    /// The amount in the CURDEF of the statement. TRNAMT is converted with the CURRATE when the
+    /// transaction has a CURRENCY. Returns None if the rate is zero or the converted amount
+    /// does not fit.
+    pub fn statement_amount(&self) -> Option<Amount> {
+        match &self.currency {
+            Some(currency) if currency.rate.is_zero() => None,
+            Some(currency) => self
+                .trans_amount
+                .checked_mul(&currency.rate)?
+                .checked_round(self.trans_amount.scale()),
+            None => Some(self.trans_amount),
+        }
+    }
+
+    /// The amount in the currency the transaction was made in. TRNAMT is converted with the
+    /// CURRATE when the transaction has an ORIGCURRENCY. Returns None if the rate is zero or
+    /// the converted amount does not fit.
+    pub fn original_amount(&self) -> Option<Amount> {
+        match &self.orig_currency {
+            Some(currency) => self
+                .trans_amount
+                .checked_div(&currency.rate, self.trans_amount.scale()),
+            None => Some(self.trans_amount),
+        }
+    }
+
+    // Builds the flattened transaction for the account the statement is for
+    fn to_transaction(
+        &self,
+        account_id: &str,
+        account_type: &AccountType,
+        bank_id: &str,
+        currency: &str,
+    ) -> Transaction {
+        Transaction {
+            trans_type: self.trans_type.clone(),
+            dt_posted: self.dt_posted.clone(),
+            trans_amount: self.trans_amount,
+            fit_id: self.fit_id.clone(),
+            correct_fit_id: self.correct_fit_id.clone().unwrap_or_default(),
+            dt_user: self.dt_user.clone(),
+            dt_avail: self.dt_avail.clone(),
+            srvr_tid: self.srvr_tid.clone().unwrap_or_default(),
+            ref_num: self.ref_num.clone().unwrap_or_default(),
+            payee_id: self.payee_id.clone().unwrap_or_default(),
+            sic: self.sic.clone().unwrap_or_default(),
+            name: self.payee_name().to_string(),
+            payee: self.payee.clone(),
+            ext_name: self.ext_name.clone().unwrap_or_default(),
+            memo: self.memo.clone().unwrap_or_default(),
+            check_num: self.check_num.clone().unwrap_or_default(),
+            account_id: account_id.to_string(),
+            account_type: account_type.clone(),
+            bank_id: bank_id.to_string(),
+            currency: currency.to_string(),
+            statement_amount: self.statement_amount(),
+            original_amount: self.original_amount(),
+            original_currency: self
+                .currency
+                .as_ref()
+                .or(self.orig_currency.as_ref())
+                .map_or(currency, |x| x.symbol.as_str())
+                .to_string(),
+            currency_rate: self
+                .currency
+                .as_ref()
+                .or(self.orig_currency.as_ref())
+                .map(|x| x.rate),
+            counterparty_account_id: match (&self.bankacctto, &self.ccacctto) {
+                (Some(bankacctto), _) => bankacctto.acct_id.clone(),
+                (None, Some(ccacctto)) => ccacctto.acct_id.clone(),
+                (None, None) => String::new(),
+            },
+            counterparty_account_type: match (&self.bankacctto, &self.ccacctto) {
+                (Some(bankacctto), _) => Some(bankacctto.acct_type.clone()),
+                (None, Some(_)) => Some(AccountType::CreditCard),
+                (None, None) => None,
+            },
+            counterparty_bank_id: self
+                .bankacctto
+                .as_ref()
+                .and_then(|x| x.bank_id.clone())
+                .unwrap_or_default(),
+        }
+    }
 }
 
 impl Display for Stmttrn {
@@ -113,50 +557,141 @@ impl Display for Stmttrn {
             "Stmttrn {{ dt_posted: {}, trans_amount: {}, name: {}, memo: {} }}",
             self.dt_posted,
             self.trans_amount,
-            self.name,
+            self.payee_name(),
             self.memo.as_deref().unwrap_or(""),
         )
     }
 }
 
-impl QFX {
-    // Generate
-    pub fn new_from_file(file_path: &str) -> Result<Self, QFXParsingError> {
-        let mut file =
-            File::open(file_path).map_err(|e| QFXParsingError::FileNotFound(e.to_string()))?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)
-            .map_err(|e| QFXParsingError::FileReadError(e.to_string()))?;
-
-        // Find the starting <OFX> tag and remove everything before it.
-        // TODO: The information before that tag might actually be useful!
-        if let Some(start_index) = contents.find("<OFX>") {
-            contents = contents[start_index..].to_string();
-        } else {
-            return Err(QFXParsingError::UnexpectedToken(
-                "Could not find the <OFX> tag in the file".to_string(),
-            ));
-        }
+impl FromStr for QFX {
+    type Err = QFXParsingError;
 
-        let mut tokens = tokenize(&contents);
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "OFX" => {
-                    return Ok(QFX::parse(&mut tokens)?);
-                }
-                _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found an unexpected token. Expecting: OFX, Found {}",
-                        contents.to_string()
-                    )));
-                }
-            }
+    /// Parses a QFX document that has already been read in to memory.
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        Self::from_str_with_options(contents, &ParseOptions::default()).map(|(qfx, _)| qfx)
+    }
+}
+
+// Parses the <OFX> aggregate that makes up the body of the document.
+fn parse_document(tokens: &mut TokenStream) -> Result<QFX, QFXParsingError> {
+    match tokens.next_token()? {
+        Some(token) if token.kind == TokenKind::StartTag("OFX") => QFX::parse(tokens),
+        Some(token) => {
+            // Error case, unknown token seen
+            Err(QFXParsingError::UnexpectedToken {
+                message: format!("Expecting: OFX, Found {}", token),
+                expected: Some("OFX".to_string()),
+                found: Some(token.to_string()),
+                context: Box::default(),
+            })
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        None => Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the OFX token to start parsing the file"
                 .to_string(),
-        ));
+            "OFX",
+        )),
+    }
+}
+
+impl QFX {
+    /// Reads and parses the QFX file at the given path.
+    pub fn new_from_file(file_path: &str) -> Result<Self, QFXParsingError> {
+        Self::from_path(file_path)
+    }
+
+    /// Reads and parses the QFX file at the given path.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, QFXParsingError> {
+        Self::from_path_with_options(path, &ParseOptions::default()).map(|(qfx, _)| qfx)
+    }
+
+    /// Reads and parses the QFX file at the given path using the given options.
+    pub fn from_path_with_options(
+        path: impl AsRef<Path>,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<ParseWarning>), QFXParsingError> {
+        let file = File::open(path).map_err(|e| QFXParsingError::FileNotFound {
+            message: e.to_string(),
+        })?;
+        Self::from_reader_with_options(file, options)
+    }
+
+    /// Reads the reader to the end and parses its contents as a QFX document. Useful for
+    /// upload bodies or attachments that never touch the disk.
+    pub fn from_reader(reader: impl Read) -> Result<Self, QFXParsingError> {
+        Self::from_reader_with_options(reader, &ParseOptions::default()).map(|(qfx, _)| qfx)
+    }
+
+    /// Reads the reader to the end and parses its contents using the given options.
+    pub fn from_reader_with_options(
+        mut reader: impl Read,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<ParseWarning>), QFXParsingError> {
+        let mut contents = vec![];
+        reader
+            .read_to_end(&mut contents)
+            .map_err(|e| QFXParsingError::FileReadError {
+                message: e.to_string(),
+            })?;
+        Self::from_bytes_with_options(&contents, options)
+    }
+
+    /// Parses a QFX document from raw bytes. The bytes are decoded using the byte order mark
+    /// or the ENCODING/CHARSET declared in the header.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, QFXParsingError> {
+        Self::from_bytes_with_options(bytes, &ParseOptions::default()).map(|(qfx, _)| qfx)
+    }
+
+    /// Parses a QFX document from raw bytes using the given options.
+    pub fn from_bytes_with_options(
+        bytes: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<ParseWarning>), QFXParsingError> {
+        Self::from_str_with_options(&Self::decode(bytes, options)?, options)
+    }
+
+    /// Decodes the raw bytes of a QFX document in to text the same way `from_bytes` does. The
+    /// positions in errors are offsets in to this text, so it is what has to be passed to
+    /// `QFXParsingError::render` for files that are not UTF-8.
+    pub fn decode<'a>(
+        bytes: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<Cow<'a, str>, QFXParsingError> {
+        encoding::decode(bytes, options.encoding)
+    }
+
+    /// Parses a QFX document that has already been read in to memory using the given options.
+    /// Returns the warnings for everything that was skipped along with the document.
+    pub fn from_str_with_options(
+        contents: &str,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<ParseWarning>), QFXParsingError> {
+        let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+        // Everything before the starting <OFX> tag is the header.
+        let Some(start_index) = contents.find("<OFX>") else {
+            return Err(QFXParsingError::UnexpectedToken {
+                message: "Could not find the <OFX> tag in the file".to_string(),
+                expected: Some("OFX".to_string()),
+                found: None,
+                context: Box::default(),
+            });
+        };
+        let header = &contents[..start_index];
+        let header = if header.trim().is_empty() {
+            None
+        } else {
+            Some(OfxHeader::parse(header)?)
+        };
+
+        let mut tokens = TokenStream::new(
+            Lexer::new_at(
+                contents,
+                start_index,
+                header.as_ref().map_or(OfxFlavour::Sgml, |x| x.flavour()),
+            ),
+            options,
+        );
+        let qfx = parse_document(&mut tokens).map_err(|e| e.with_context(tokens.context()))?;
+        Ok((QFX { header, ..qfx }, tokens.take_warnings()))
     }
 
     /// This is a rather expensive function. It returns a vector containing all the transactions in the built qfx file.
@@ -164,74 +699,35 @@ impl QFX {
         let mut transactions = vec![];
 
         if let Some(bank_transactions) = &self.bank_msg_srs_v1 {
-            let acct_id = bank_transactions
-                .stmttrns
-                .stmtrs
-                .bankacctfrom
-                .acct_id
-                .clone();
-            let acct_type = bank_transactions
+            // Responses with an error status do not have a statement
+            for stmtrs in bank_transactions
                 .stmttrns
-                .stmtrs
-                .bankacctfrom
-                .acct_type
-                .clone();
-            let currency = bank_transactions
-                .stmttrns
-                .stmtrs
-                .currency
-                .clone()
-                .unwrap_or_default();
-            for stmttrn in &bank_transactions.stmttrns.stmtrs.banktranslist.transactions {
-                transactions.push(Transaction {
-                    trans_type: stmttrn.trans_type.clone(),
-                    dt_posted: stmttrn.dt_posted,
-                    trans_amount: stmttrn.trans_amount,
-                    fit_id: stmttrn.fit_id.clone(),
-                    correct_fit_id: stmttrn.correct_fit_id.clone().unwrap_or_default(),
-                    name: stmttrn.name.clone(),
-                    memo: stmttrn.memo.clone().unwrap_or_default(),
-                    check_num: stmttrn.check_num.clone().unwrap_or_default(),
-                    account_id: acct_id.clone(),
-                    account_type: acct_type.clone(),
-                    currency: currency.clone(),
-                });
+                .iter()
+                .filter_map(|x| x.stmtrs.as_ref())
+            {
+                let acct_id = stmtrs.bankacctfrom.acct_id.clone();
+                let acct_type = stmtrs.bankacctfrom.acct_type.clone();
+                let bank_id = stmtrs.bankacctfrom.bank_id.clone().unwrap_or_default();
+                let currency = stmtrs.currency.clone().unwrap_or_default();
+                for stmttrn in &stmtrs.banktranslist.transactions {
+                    transactions
+                        .push(stmttrn.to_transaction(&acct_id, &acct_type, &bank_id, &currency));
+                }
             }
         }
 
         if let Some(cc_transactions) = &self.credit_card_msg_srs_v1 {
-            let acct_id = cc_transactions
+            for ccstmtrs in cc_transactions
                 .ccstmttrns
-                .ccstmtrs
-                .ccacctfrom
-                .acct_id
-                .clone();
-            let acct_type = "".to_string();
-            let currency = cc_transactions
-                .ccstmttrns
-                .ccstmtrs
-                .currency
-                .clone()
-                .unwrap_or_default();
-            for stmttrn in &cc_transactions
-                .ccstmttrns
-                .ccstmtrs
-                .banktranslist
-                .transactions
+                .iter()
+                .filter_map(|x| x.ccstmtrs.as_ref())
             {
-                transactions.push(Transaction {
-                    trans_type: stmttrn.trans_type.clone(),
-                    dt_posted: stmttrn.dt_posted,
-                    trans_amount: stmttrn.trans_amount,
-                    fit_id: stmttrn.fit_id.clone(),
-                    correct_fit_id: stmttrn.correct_fit_id.clone().unwrap_or_default(),
-                    name: stmttrn.name.clone(),
-                    memo: stmttrn.memo.clone().unwrap_or_default(),
-                    check_num: stmttrn.check_num.clone().unwrap_or_default(),
-                    account_id: acct_id.clone(),
-                    account_type: acct_type.clone(),
-                    currency: currency.clone(),
-                });
+                let acct_id = ccstmtrs.ccacctfrom.acct_id.clone();
+                let acct_type = AccountType::CreditCard;
+                let currency = ccstmtrs.currency.clone().unwrap_or_default();
+                for stmttrn in &ccstmtrs.banktranslist.transactions {
+                    transactions.push(stmttrn.to_transaction(&acct_id, &acct_type, "", &currency));
+                }
             }
         }
 
@@ -240,540 +736,733 @@ impl QFX {
 }
 
 impl<'a> Parseable<'a> for QFX {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &["SIGNONMSGSRSV1", "BANKMSGSRSV1", "CREDITCARDMSGSRSV1"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
         let mut qfx = QFX {
+            header: None,
             sign_on_msg_srs_v1: None,
             credit_card_msg_srs_v1: None,
             bank_msg_srs_v1: None,
         };
-        while let Some(contents) = tokens.next() {
-            match contents {
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
                 // TODO: There are a few more types left to account for!
-                "SIGNONMSGSRSV1" => {
-                    if let Some(_) = qfx.sign_on_msg_srs_v1 {
-                        return Err(QFXParsingError::UnexpectedToken(
+                TokenKind::StartTag("SIGNONMSGSRSV1") => {
+                    if qfx.sign_on_msg_srs_v1.is_some() {
+                        return Err(QFXParsingError::unexpected_token(
                             "The value for sign on message srs v1 is already set".to_string(),
+                            token,
                         ));
                     }
                     qfx.sign_on_msg_srs_v1 = Some(SignOnMsgSrsV1::parse(tokens)?);
                 }
-                "CREDITCARDMSGSRSV1" => {
+                TokenKind::StartTag("CREDITCARDMSGSRSV1") => {
+                    if qfx.credit_card_msg_srs_v1.is_some() {
+                        return Err(QFXParsingError::unexpected_token(
+                            "The value for credit card message srs v1 is already set".to_string(),
+                            token,
+                        ));
+                    }
                     qfx.credit_card_msg_srs_v1 = Some(CCMsgSrsV1::parse(tokens)?);
                 }
-                "BANKMSGSRSV1" => {
-                    if let Some(_) = qfx.bank_msg_srs_v1 {
-                        return Err(QFXParsingError::UnexpectedToken(
+                TokenKind::StartTag("BANKMSGSRSV1") => {
+                    if qfx.bank_msg_srs_v1.is_some() {
+                        return Err(QFXParsingError::unexpected_token(
                             "The value for bank message srs v1 is already set".to_string(),
+                            token,
                         ));
                     }
                     qfx.bank_msg_srs_v1 = Some(BankMsgSrsV1::parse(tokens)?);
                 }
-                "/OFX" => {
+                TokenKind::EndTag("OFX") => {
                     return Ok(qfx);
                 }
                 _ => {
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected input in the OFX tag, Found Token: {}",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!(
+                                "Found unexpected input in the OFX tag, Found Token: {}",
+                                token
+                            ),
+                            token,
+                            Self::TAGS,
+                            "OFX",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/OFX' token to end parsing".to_string(),
-        ));
+            "/OFX",
+        ))
+    }
+}
+
+impl Status {
+    /// True when the request succeeded. That is a code of 0, or 1 for a client that is already
+    /// up to date, with a severity other than ERROR.
+    pub fn is_success(&self) -> bool {
+        matches!(self.code, StatusCode::Success | StatusCode::UpToDate)
+            && self.severity != Severity::Error
     }
 }
 
 impl<'a> Parseable<'a> for Status {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &["CODE", "SEVERITY", "MESSAGE"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
         let mut s_code = None;
         let mut s_severity = None;
         let mut s_message = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "CODE" => {
-                    if let Some(code) = tokens.next() {
-                        // TODO: What if there is no other stuff? Should error out.
-                        s_code = Some(code.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF("Expected token following the CODE token in the STATUS type of CREDITCARDMSGSRSV1".to_string()));
-                    }
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("CODE") => {
+                    s_code = Some(StatusCode::from(tokens.value("CODE")?.as_str()));
                 }
-                "SEVERITY" => {
-                    if let Some(severity) = tokens.next() {
-                        s_severity = Some(severity.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the SEVERITY token in the STATUS type"
-                                .to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("SEVERITY") => {
+                    s_severity = Some(Severity::from(tokens.value("SEVERITY")?.as_str()));
                 }
-                "MESSAGE" => {
-                    if let Some(message) = tokens.next() {
-                        s_message = Some(message.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the MESSAGE token in the STATUS type"
-                                .to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("MESSAGE") => {
+                    s_message = Some(tokens.value("MESSAGE")?);
                 }
-                "/STATUS" => {
-                    return Ok(Self {
-                        code: s_code.ok_or(QFXParsingError::MissingRequiredValue(
+                TokenKind::EndTag("STATUS") => {
+                    let status = Self {
+                        code: s_code.ok_or(QFXParsingError::missing_value(
                             "Missing CODE in STATUS".to_string(),
+                            "CODE",
                         ))?,
-                        severity: s_severity.ok_or(QFXParsingError::MissingRequiredValue(
+                        severity: s_severity.ok_or(QFXParsingError::missing_value(
                             "Missing SEVERITY in STATUS".to_string(),
+                            "SEVERITY",
                         ))?,
                         message: s_message,
-                    });
+                    };
+                    if tokens.server_errors() && status.severity == Severity::Error {
+                        let message = status.message.clone().unwrap_or_else(|| {
+                            status.code.description().unwrap_or_default().to_string()
+                        });
+                        return Err(QFXParsingError::ServerError {
+                            code: status.code,
+                            message,
+                            context: Box::default(),
+                        });
+                    }
+                    return Ok(status);
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the STATUS type of CREDITCARDMSGSRSV1",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the STATUS type", token),
+                            token,
+                            Self::TAGS,
+                            "STATUS",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF("Found unexpected EOF. Was still expecting the '/STATUS' token in the CREDITCARDMSGSRSV1 subtype".to_string()));
+        Err(QFXParsingError::unexpected_eof(
+            "Found unexpected EOF. Was still expecting the '/STATUS' token".to_string(),
+            "/STATUS",
+        ))
     }
 }
 
 impl<'a> Parseable<'a> for LedgerBal {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &["BALAMT", "DTASOF"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
         let mut s_balance_amount = None;
         let mut s_dt_as_of = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "BALAMT" => {
-                    if let Some(balance_amount) = tokens.next() {
-                        s_balance_amount = Some(balance_amount.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected value following BALAMT in LEDGERBAL".to_string(),
-                        ));
-                    }
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("BALAMT") => {
+                    let balance_amount = tokens.value("BALAMT")?;
+                    s_balance_amount = Some(balance_amount.parse::<Amount>().map_err(|e| {
+                        QFXParsingError::invalid_amount(
+                            format!("Invalid balance amount {} with {}", balance_amount, e),
+                            &balance_amount,
+                        )
+                    })?);
                 }
-                "DTASOF" => {
-                    if let Some(dt_as_of) = tokens.next() {
-                        s_dt_as_of = Some(parse_ofx_datetime(dt_as_of).map_err(|e| {
-                            QFXParsingError::UnexpectedDateFormat(format!(
-                                "Failed to parse datetime for DTASOF with {}",
-                                e
-                            ))
-                        })?);
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the DTASOF token".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("DTASOF") => {
+                    s_dt_as_of = Some(tokens.datetime("DTASOF")?);
                 }
-                "/LEDGERBAL" => {
+                TokenKind::EndTag("LEDGERBAL") => {
                     return Ok(Self {
-                        balance_amount: s_balance_amount.ok_or(
-                            QFXParsingError::MissingRequiredValue(
-                                "Missing BALAMT in LEDGERBAL".to_string(),
-                            ),
-                        )?,
-                        dt_as_of: s_dt_as_of.ok_or(QFXParsingError::MissingRequiredValue(
+                        balance_amount: s_balance_amount.ok_or(QFXParsingError::missing_value(
+                            "Missing BALAMT in LEDGERBAL".to_string(),
+                            "BALAMT",
+                        ))?,
+                        dt_as_of: s_dt_as_of.ok_or(QFXParsingError::missing_value(
                             "Missing DTASOF in LEDGERBAL".to_string(),
+                            "DTASOF",
                         ))?,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the LEDGERBAL type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the LEDGERBAL type", token),
+                            token,
+                            Self::TAGS,
+                            "LEDGERBAL",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
-            "Found unexpected EOF. Was still expecting the '/AVAILBAL' token".to_string(),
-        ));
+        Err(QFXParsingError::unexpected_eof(
+            "Found unexpected EOF. Was still expecting the '/LEDGERBAL' token".to_string(),
+            "/LEDGERBAL",
+        ))
     }
 }
 
 impl<'a> Parseable<'a> for AvailableBalance {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &["BALAMT", "DTASOF"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
         let mut s_balance_amount = None;
         let mut s_dt_as_of = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "BALAMT" => {
-                    if let Some(balance_amount) = tokens.next() {
-                        s_balance_amount = Some(balance_amount.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the BALAMT token".to_string(),
-                        ));
-                    }
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("BALAMT") => {
+                    let balance_amount = tokens.value("BALAMT")?;
+                    s_balance_amount = Some(balance_amount.parse::<Amount>().map_err(|e| {
+                        QFXParsingError::invalid_amount(
+                            format!("Invalid balance amount {} with {}", balance_amount, e),
+                            &balance_amount,
+                        )
+                    })?);
                 }
-                "DTASOF" => {
-                    if let Some(dt_as_of) = tokens.next() {
-                        s_dt_as_of = Some(parse_ofx_datetime(dt_as_of).map_err(|e| {
-                            QFXParsingError::UnexpectedDateFormat(format!(
-                                "Failed to parse datetime for DTASOF with {}",
-                                e
-                            ))
-                        })?);
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the DTASOF token".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("DTASOF") => {
+                    s_dt_as_of = Some(tokens.datetime("DTASOF")?);
                 }
-                "/AVAILBAL" => {
+                TokenKind::EndTag("AVAILBAL") => {
                     return Ok(Self {
-                        balance_amount: s_balance_amount.ok_or(
-                            QFXParsingError::MissingRequiredValue(
-                                "Missing BALAMT in AVAILBAL".to_string(),
-                            ),
-                        )?,
-                        dt_as_of: s_dt_as_of.ok_or(QFXParsingError::MissingRequiredValue(
+                        balance_amount: s_balance_amount.ok_or(QFXParsingError::missing_value(
+                            "Missing BALAMT in AVAILBAL".to_string(),
+                            "BALAMT",
+                        ))?,
+                        dt_as_of: s_dt_as_of.ok_or(QFXParsingError::missing_value(
                             "Missing DTASOF in AVAILBAL".to_string(),
+                            "DTASOF",
                         ))?,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the AVAILBAL type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the AVAILBAL type", token),
+                            token,
+                            Self::TAGS,
+                            "AVAILBAL",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/AVAILBAL' token".to_string(),
-        ));
+            "/AVAILBAL",
+        ))
     }
 }
 
 impl<'a> Parseable<'a> for BankTranList {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
-        let mut s_dt_start: Option<DateTime<Utc>> = None;
-        let mut s_dt_end: Option<DateTime<Utc>> = None;
+    const TAGS: &'static [&'static str] = &["DTSTART", "DTEND", "STMTTRN"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
+        let mut s_dt_start: Option<OfxDateTime> = None;
+        let mut s_dt_end: Option<OfxDateTime> = None;
         let mut s_transactions: Vec<Stmttrn> = vec![];
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "DTSTART" => {
-                    if let Some(dt_start) = tokens.next() {
-                        s_dt_start = Some(parse_ofx_datetime(dt_start).map_err(|e| {
-                            QFXParsingError::UnexpectedDateFormat(format!(
-                                "Failed to parse datetime for DTSTART with {}",
-                                e
-                            ))
-                        })?);
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the DTSTART token".to_string(),
-                        ));
-                    }
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("DTSTART") => {
+                    s_dt_start = Some(tokens.datetime("DTSTART")?);
                 }
-                "DTEND" => {
-                    if let Some(dt_end) = tokens.next() {
-                        s_dt_end = Some(parse_ofx_datetime(dt_end).map_err(|e| {
-                            QFXParsingError::UnexpectedDateFormat(format!(
-                                "Failed to parse datetime for DTEND with {}",
-                                e
-                            ))
-                        })?);
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the DTEND token".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("DTEND") => {
+                    s_dt_end = Some(tokens.datetime("DTEND")?);
                 }
-                "STMTTRN" => {
+                TokenKind::StartTag("STMTTRN") => {
                     s_transactions.push(Stmttrn::parse(tokens)?);
                 }
-                "/BANKTRANLIST" => {
+                TokenKind::EndTag("BANKTRANLIST") => {
                     return Ok(Self {
-                        dt_start: s_dt_start.ok_or(QFXParsingError::MissingRequiredValue(
+                        dt_start: s_dt_start.ok_or(QFXParsingError::missing_value(
                             "Missing DTSTART in BANKTRANLIST".to_string(),
+                            "DTSTART",
                         ))?,
-                        dt_end: s_dt_end.ok_or(QFXParsingError::MissingRequiredValue(
+                        dt_end: s_dt_end.ok_or(QFXParsingError::missing_value(
                             "Missing DTEND in BANKTRANLIST".to_string(),
+                            "DTEND",
                         ))?,
                         transactions: s_transactions,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the BANKTRANLIST type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the BANKTRANLIST type", token),
+                            token,
+                            Self::TAGS,
+                            "BANKTRANLIST",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/BANKTRANLIST' token".to_string(),
-        ));
+            "/BANKTRANLIST",
+        ))
     }
 }
 
 impl<'a> Parseable<'a> for Stmttrn {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
-        let mut s_trans_type: Option<String> = None;
-        let mut s_dt_posted: Option<DateTime<Utc>> = None;
-        let mut s_trans_amount: Option<f64> = None;
+    const TAGS: &'static [&'static str] = &[
+        "TRNTYPE",
+        "DTPOSTED",
+        "TRNAMT",
+        "FITID",
+        "CORRECTFITID",
+        "CORRECTACTION",
+        "DTUSER",
+        "DTAVAIL",
+        "SRVRTID",
+        "REFNUM",
+        "PAYEEID",
+        "SIC",
+        "NAME",
+        "PAYEE",
+        "EXTDNAME",
+        "MEMO",
+        "CHECKNUM",
+        "BANKACCTTO",
+        "CCACCTTO",
+        "CURRENCY",
+        "ORIGCURRENCY",
+    ];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
+        let mut s_trans_type: Option<TransactionType> = None;
+        let mut s_dt_posted: Option<OfxDateTime> = None;
+        let mut s_trans_amount: Option<Amount> = None;
         let mut s_fit_id: Option<String> = None;
         let mut s_correct_fit_id: Option<String> = None;
+        let mut s_dt_user: Option<OfxDateTime> = None;
+        let mut s_dt_avail: Option<OfxDateTime> = None;
+        let mut s_srvr_tid: Option<String> = None;
+        let mut s_ref_num: Option<String> = None;
+        let mut s_payee_id: Option<String> = None;
+        let mut s_sic: Option<String> = None;
         let mut s_name: Option<String> = None;
+        let mut s_payee: Option<Payee> = None;
+        let mut s_ext_name: Option<String> = None;
+        let mut s_bankacctto: Option<Bankacctfrom> = None;
+        let mut s_ccacctto: Option<Ccacctfrom> = None;
+        let mut s_currency: Option<Currency> = None;
+        let mut s_orig_currency: Option<Currency> = None;
         let mut s_memo: Option<String> = None;
         let mut s_correct_action: Option<String> = None;
         let mut s_check_num: Option<String> = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "TRNTYPE" => {
-                    if let Some(trans_type) = tokens.next() {
-                        s_trans_type = Some(trans_type.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the TRNTYPE token in STMTTRN".to_string(),
-                        ));
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("TRNTYPE") => {
+                    let trans_type = TransactionType::from(tokens.value("TRNTYPE")?.as_str());
+                    if !trans_type.is_known() {
+                        // Kept as it is so that types a financial institution made up are not lost
+                        tokens.keep_invalid_value(
+                            "TRNTYPE",
+                            format!("{} is not a transaction type in the OFX spec", trans_type),
+                        );
                     }
+                    s_trans_type = Some(trans_type);
                 }
-                "DTPOSTED" => {
-                    if let Some(dt_posted) = tokens.next() {
-                        s_dt_posted = Some(parse_ofx_datetime(dt_posted).map_err(|e| {
-                            QFXParsingError::UnexpectedDateFormat(format!(
-                                "Failed to parse datetime for DTPOSTED with {}",
-                                e
-                            ))
-                        })?);
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the DTPOSTED token in STMTTRN".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("DTPOSTED") => {
+                    s_dt_posted = Some(tokens.datetime("DTPOSTED")?);
                 }
-                "TRNAMT" => {
-                    if let Some(trans_amount) = tokens.next() {
-                        // TODO: This seems to parse unrepresentable values too. Figure out a way to properly parse the values.
-                        s_trans_amount = Some(trans_amount.parse::<f64>().map_err(|_| {
-                            QFXParsingError::InvalidTransactionAmount(format!(
-                                "Invalid transaction amount {}",
-                                trans_amount
-                            ))
-                        })?);
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the TRNAMT token in STMTTRN".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("TRNAMT") => {
+                    let trans_amount = tokens.value("TRNAMT")?;
+                    s_trans_amount = Some(trans_amount.parse::<Amount>().map_err(|e| {
+                        QFXParsingError::invalid_amount(
+                            format!("Invalid transaction amount {} with {}", trans_amount, e),
+                            &trans_amount,
+                        )
+                    })?);
                 }
-                "FITID" => {
-                    if let Some(fit_id) = tokens.next() {
-                        s_fit_id = Some(fit_id.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the FITID token in STMTTRN".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("FITID") => {
+                    s_fit_id = Some(tokens.value("FITID")?);
                 }
-                "CORRECTFITID" => {
-                    if let Some(correct_fit_id) = tokens.next() {
-                        s_correct_fit_id = Some(correct_fit_id.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the CORRECTFITID token in STMTTRN"
-                                .to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("CORRECTFITID") => {
+                    s_correct_fit_id = Some(tokens.value("CORRECTFITID")?);
                 }
-                "CORRECTACTION" => {
-                    if let Some(action) = tokens.next() {
-                        s_correct_action = Some(action.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the CORRECTACTION token in STMTTRN"
-                                .to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("CORRECTACTION") => {
+                    s_correct_action = Some(tokens.value("CORRECTACTION")?);
                 }
-                "NAME" => {
-                    if let Some(name) = tokens.next() {
-                        s_name = Some(name.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the NAME token in STMTTRN".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("DTUSER") => {
+                    s_dt_user = Some(tokens.datetime("DTUSER")?);
                 }
-                "MEMO" => {
-                    if let Some(memo) = tokens.next() {
-                        s_memo = Some(memo.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the MEMO token in STMTTRN".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("DTAVAIL") => {
+                    s_dt_avail = Some(tokens.datetime("DTAVAIL")?);
+                }
+                TokenKind::StartTag("SRVRTID") => {
+                    s_srvr_tid = Some(tokens.value("SRVRTID")?);
+                }
+                TokenKind::StartTag("REFNUM") => {
+                    s_ref_num = Some(tokens.value("REFNUM")?);
+                }
+                TokenKind::StartTag("PAYEEID") => {
+                    s_payee_id = Some(tokens.value("PAYEEID")?);
+                }
+                TokenKind::StartTag("SIC") => {
+                    s_sic = Some(tokens.value("SIC")?);
+                }
+                TokenKind::StartTag("NAME") => {
+                    s_name = Some(tokens.value("NAME")?);
+                }
+                TokenKind::StartTag("PAYEE") => {
+                    s_payee = Some(Payee::parse(tokens)?);
+                }
+                TokenKind::StartTag("EXTDNAME") => {
+                    s_ext_name = Some(tokens.value("EXTDNAME")?);
+                }
+                TokenKind::StartTag("MEMO") => {
+                    s_memo = Some(tokens.value("MEMO")?);
+                }
+                TokenKind::StartTag("CHECKNUM") => {
+                    s_check_num = Some(tokens.value("CHECKNUM")?);
+                }
+                TokenKind::StartTag("BANKACCTTO") => {
+                    s_bankacctto = Some(Bankacctfrom::parse_aggregate(tokens, "BANKACCTTO")?);
+                }
+                TokenKind::StartTag("CCACCTTO") => {
+                    s_ccacctto = Some(Ccacctfrom::parse_aggregate(tokens, "CCACCTTO")?);
+                }
+                TokenKind::StartTag("CURRENCY") => {
+                    s_currency = Some(Currency::parse_aggregate(tokens, "CURRENCY")?);
+                }
+                TokenKind::StartTag("ORIGCURRENCY") => {
+                    s_orig_currency = Some(Currency::parse_aggregate(tokens, "ORIGCURRENCY")?);
                 }
-                "CHECKNUM" => {
-                    if let Some(check_num) = tokens.next() {
-                        s_check_num = Some(check_num.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the CHECKNUM token in STMTTRN".to_string(),
+                TokenKind::EndTag("STMTTRN") => {
+                    if s_name.is_none() && s_payee.is_none() {
+                        return Err(QFXParsingError::missing_value(
+                            "NAME or PAYEE value is required in STMTTRN".to_string(),
+                            "NAME",
                         ));
                     }
-                }
-                "/STMTTRN" => {
                     return Ok(Self {
-                        trans_type: s_trans_type.ok_or(QFXParsingError::MissingRequiredValue(
-                            "TRANSTYPE value is required in STMTRN".to_string(),
-                        ))?, // TODO: Need to get rid of these panics
-                        dt_posted: s_dt_posted.ok_or(QFXParsingError::MissingRequiredValue(
-                            "DTPOSTED value is required in STMTRN".to_string(),
+                        trans_type: s_trans_type.ok_or(QFXParsingError::missing_value(
+                            "TRNTYPE value is required in STMTTRN".to_string(),
+                            "TRNTYPE",
                         ))?,
-                        trans_amount: s_trans_amount.ok_or(
-                            QFXParsingError::MissingRequiredValue(
-                                "TRNSAMT value is required in STMTRN".to_string(),
-                            ),
-                        )?,
-                        fit_id: s_fit_id.ok_or(QFXParsingError::MissingRequiredValue(
-                            "FITID value is required in STMTRN".to_string(),
+                        dt_posted: s_dt_posted.ok_or(QFXParsingError::missing_value(
+                            "DTPOSTED value is required in STMTTRN".to_string(),
+                            "DTPOSTED",
+                        ))?,
+                        trans_amount: s_trans_amount.ok_or(QFXParsingError::missing_value(
+                            "TRNAMT value is required in STMTTRN".to_string(),
+                            "TRNAMT",
+                        ))?,
+                        fit_id: s_fit_id.ok_or(QFXParsingError::missing_value(
+                            "FITID value is required in STMTTRN".to_string(),
+                            "FITID",
                         ))?,
                         correct_fit_id: s_correct_fit_id,
                         correct_action: s_correct_action,
-                        name: s_name.ok_or(QFXParsingError::MissingRequiredValue(
-                            "NAME value is required in STMTRN".to_string(),
-                        ))?,
+                        dt_user: s_dt_user,
+                        dt_avail: s_dt_avail,
+                        srvr_tid: s_srvr_tid,
+                        ref_num: s_ref_num,
+                        payee_id: s_payee_id,
+                        sic: s_sic,
+                        name: s_name,
+                        payee: s_payee,
+                        ext_name: s_ext_name,
                         memo: s_memo,
                         check_num: s_check_num,
+                        bankacctto: s_bankacctto,
+                        ccacctto: s_ccacctto,
+                        currency: s_currency,
+                        orig_currency: s_orig_currency,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the STMTTRN type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the STMTTRN type", token),
+                            token,
+                            Self::TAGS,
+                            "STMTTRN",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::MissingRequiredValue(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/STMTTRN' token".to_string(),
-        ));
+            "/STMTTRN",
+        ))
     }
 }
 
-// Parses a date time in the OFX standard format. Expects something like: 20250725143000[+7:PDT], 20250725T143000[+7:PDT], 20250725T143000Z, 20250725143000
-// TODO: For the time being this will ignore the timezone at the end and treat everything in UTC time. %Z in chrono
-// does not have the concept of timezones because it claims ambiguitiy (CST = China Standard Time or Central Standard Time)
-fn parse_ofx_datetime(s: &str) -> Result<DateTime<Utc>, ParseError> {
-    // Remove the ending [0:PDT] stuff.
-    let mut s = remove_last_bracketed(s);
-    s = s.strip_suffix("Z").unwrap_or(s);
-
-    // Attempt to parse with milliseconds
-    let format_with_tz_ms = "%Y%m%d%H%M%S%.f";
-    if let Ok(dt) = NaiveDateTime::parse_from_str(s, format_with_tz_ms) {
-        return Ok(dt.and_utc());
+impl Currency {
+    // CURRENCY and ORIGCURRENCY have the same contents, so the end tag is passed in
+    fn parse_aggregate(tokens: &mut TokenStream, end_tag: &str) -> Result<Self, QFXParsingError> {
+        let mut s_rate: Option<Amount> = None;
+        let mut s_symbol: Option<String> = None;
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("CURRATE") => {
+                    let rate = tokens.value("CURRATE")?;
+                    let parsed = rate.parse::<Amount>().map_err(|e| {
+                        QFXParsingError::invalid_amount(
+                            format!("Invalid currency rate {} with {}", rate, e),
+                            &rate,
+                        )
+                    })?;
+                    if parsed.is_zero() {
+                        // Amounts can not be converted with it but the transaction is still usable
+                        tokens.keep_invalid_value(
+                            "CURRATE",
+                            format!("The currency rate of {} can not be zero", end_tag),
+                        );
+                    }
+                    s_rate = Some(parsed);
+                }
+                TokenKind::StartTag("CURSYM") => {
+                    s_symbol = Some(tokens.value("CURSYM")?);
+                }
+                TokenKind::EndTag(tag) if tag == end_tag => {
+                    return Ok(Self {
+                        rate: s_rate.ok_or(QFXParsingError::missing_value(
+                            format!("CURRATE value is required in {}", end_tag),
+                            "CURRATE",
+                        ))?,
+                        symbol: s_symbol.ok_or(QFXParsingError::missing_value(
+                            format!("CURSYM value is required in {}", end_tag),
+                            "CURSYM",
+                        ))?,
+                    });
+                }
+                _ => {
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the {} type", token, end_tag),
+                            token,
+                            <Self as Parseable>::TAGS,
+                            end_tag,
+                        ),
+                    )?;
+                }
+            }
+        }
+        Err(QFXParsingError::unexpected_eof(
+            format!(
+                "Found unexpected EOF. Was still expecting the '/{}' token",
+                end_tag
+            ),
+            &format!("/{}", end_tag),
+        ))
     }
+}
 
-    let format_with_tz_ms = "%Y%m%dT%H%M%S%.f";
-    if let Ok(dt) = NaiveDateTime::parse_from_str(s, format_with_tz_ms) {
-        return Ok(dt.and_utc());
+impl<'a> Parseable<'a> for Currency {
+    const TAGS: &'static [&'static str] = &["CURRATE", "CURSYM"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
+        Self::parse_aggregate(tokens, "CURRENCY")
     }
+}
 
-    // Attempt to parse without any time information
-    let format_with_tz_ms = "%Y%m%dT%H%M%S";
-    if let Ok(dt) = NaiveDateTime::parse_from_str(s, format_with_tz_ms) {
-        return Ok(dt.and_utc());
+impl<'a> Parseable<'a> for Payee {
+    const TAGS: &'static [&'static str] = &[
+        "NAME",
+        "ADDR1",
+        "ADDR2",
+        "ADDR3",
+        "CITY",
+        "STATE",
+        "POSTALCODE",
+        "COUNTRY",
+        "PHONE",
+    ];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
+        let mut s_name: Option<String> = None;
+        let mut s_addr1: Option<String> = None;
+        let mut s_addr2: Option<String> = None;
+        let mut s_addr3: Option<String> = None;
+        let mut s_city: Option<String> = None;
+        let mut s_state: Option<String> = None;
+        let mut s_postal_code: Option<String> = None;
+        let mut s_country: Option<String> = None;
+        let mut s_phone: Option<String> = None;
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("NAME") => {
+                    s_name = Some(tokens.value("NAME")?);
+                }
+                TokenKind::StartTag("ADDR1") => {
+                    s_addr1 = Some(tokens.value("ADDR1")?);
+                }
+                TokenKind::StartTag("ADDR2") => {
+                    s_addr2 = Some(tokens.value("ADDR2")?);
+                }
+                TokenKind::StartTag("ADDR3") => {
+                    s_addr3 = Some(tokens.value("ADDR3")?);
+                }
+                TokenKind::StartTag("CITY") => {
+                    s_city = Some(tokens.value("CITY")?);
+                }
+                TokenKind::StartTag("STATE") => {
+                    s_state = Some(tokens.value("STATE")?);
+                }
+                TokenKind::StartTag("POSTALCODE") => {
+                    s_postal_code = Some(tokens.value("POSTALCODE")?);
+                }
+                TokenKind::StartTag("COUNTRY") => {
+                    s_country = Some(tokens.value("COUNTRY")?);
+                }
+                TokenKind::StartTag("PHONE") => {
+                    s_phone = Some(tokens.value("PHONE")?);
+                }
+                TokenKind::EndTag("PAYEE") => {
+                    // The spec requires the address and phone as well but banks often leave
+                    // them out, so only the name is enforced
+                    return Ok(Self {
+                        name: s_name.ok_or(QFXParsingError::missing_value(
+                            "NAME value is required in PAYEE".to_string(),
+                            "NAME",
+                        ))?,
+                        addr1: s_addr1,
+                        addr2: s_addr2,
+                        addr3: s_addr3,
+                        city: s_city,
+                        state: s_state,
+                        postal_code: s_postal_code,
+                        country: s_country,
+                        phone: s_phone,
+                    });
+                }
+                _ => {
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the PAYEE type", token),
+                            token,
+                            Self::TAGS,
+                            "PAYEE",
+                        ),
+                    )?;
+                }
+            }
+        }
+        Err(QFXParsingError::unexpected_eof(
+            "Found unexpected EOF. Was still expecting the '/PAYEE' token".to_string(),
+            "/PAYEE",
+        ))
     }
+}
 
-    // Attempt to parse with a seconds granularity
-    let format_with_s = "%Y%m%d%H%M%S";
-    NaiveDateTime::parse_from_str(s, format_with_s).map(|dt| dt.and_utc())
+// The character entities that can appear in OFX values and the characters they stand for.
+const ENTITIES: [(&str, char); 6] = [
+    ("&amp;", '&'),
+    ("&lt;", '<'),
+    ("&gt;", '>'),
+    ("&nbsp;", '\u{a0}'),
+    ("&quot;", '"'),
+    ("&apos;", '\''),
+];
+
+// Decodes the character entities in a value. Eg. AT&amp;T -> AT&T. Numeric character references
+// such as &#233; and &#xE9; are decoded too. Anything that is not a known entity is kept as is.
+pub(crate) fn decode_entities(value: &str) -> String {
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start_index) = rest.find('&') {
+        decoded.push_str(&rest[..start_index]);
+        rest = &rest[start_index..];
+        let entity = rest.find(';').map(|x| &rest[..=x]);
+        let character = entity.and_then(|entity| {
+            let reference = entity[1..entity.len() - 1].strip_prefix('#')?;
+            let code_point = match reference.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => reference.parse::<u32>().ok()?,
+            };
+            char::from_u32(code_point)
+        });
+        let character = character.or_else(|| {
+            ENTITIES
+                .iter()
+                .find(|(name, _)| entity == Some(*name))
+                .map(|(_, character)| *character)
+        });
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len()..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
 }
 
-// Removes trailing brackets from a string. Eg. helloworld[xxxx] -> helloworld
-fn remove_last_bracketed(s: &str) -> &str {
-    if let Some(pos) = s.rfind('[') {
-        return &s[..pos];
+/// Encodes the characters that have to be escaped in an OFX value. Eg. AT&T -> AT&amp;T. Only
+/// &amp;, &lt; and &gt; are produced since they are the only entities OFX 1.x defines. The other
+/// entities that are decoded when a file is parsed don't need to be escaped, so decoding the
+/// result gives back the original value. It should be used when values are written back out to
+/// an OFX file.
+pub fn encode_entities(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '&' => encoded.push_str("&amp;"),
+            '<' => encoded.push_str("&lt;"),
+            '>' => encoded.push_str("&gt;"),
+            _ => encoded.push(character),
+        }
     }
-    s
+    encoded
 }
 
-// Helper tokenizer for input strings
-pub(crate) fn tokenize(input: &str) -> impl Iterator<Item = &str> {
-    // Tokenize on the <> tags and iterate over the vector that is produced.
-    // TODO: VULNERABLE TO CODE INJECTION OR SOMETHING LIKE THAT? LOOK IN TO A BETTER APPROACH!
-    input
-        .split(['<', '>'].as_ref())
-        .map(|x| x.trim())
-        .filter(|x| !x.is_empty())
+// Helper to turn a snippet of a document in to the tokens the Parseable implementations expect.
+#[cfg(test)]
+pub(crate) fn tokenize(input: &str) -> TokenStream<'_> {
+    TokenStream::new(Lexer::new(input), &ParseOptions::default())
 }
 
 // TODO: TESTING: My bank gives a correct fit-id for some transactions even though it points to itself. Handle this gracefully.
+/// Module to test the Stmttrn type
 #[cfg(test)]
-mod datetime_tests {
-    use super::*;
-    use chrono::TimeZone;
-
-    #[test]
-    fn test_datetime_parser_valid_basic() {
-        // Basic OFX datetime without timezone or T
-        let dt = parse_ofx_datetime("20250725143000").unwrap();
-        let correct_dt = Utc.with_ymd_and_hms(2025, 7, 25, 14, 30, 0).unwrap();
-        assert_eq!(dt, correct_dt);
-    }
-
-    #[test]
-    fn test_datetime_parser_valid_with_t() {
-        // OFX datetime with T separator
-        let dt = parse_ofx_datetime("20250725T143000").unwrap();
-        let correct_dt = Utc.with_ymd_and_hms(2025, 7, 25, 14, 30, 0).unwrap();
-        assert_eq!(dt, correct_dt);
-    }
-
-    #[test]
-    fn test_datetime_parser_valid_with_z() {
-        // OFX datetime with Z suffix
-        let dt = parse_ofx_datetime("20250725T143000Z").unwrap();
-        let correct_dt = Utc.with_ymd_and_hms(2025, 7, 25, 14, 30, 0).unwrap();
-        assert_eq!(dt, correct_dt);
-    }
-
-    #[test]
-    fn test_datetime_parser_valid_with_bracketed() {
-        // OFX datetime with bracketed timezone info
-        let dt = parse_ofx_datetime("20250725143000[+7:PDT]").unwrap();
-        let correct_dt = Utc.with_ymd_and_hms(2025, 7, 25, 14, 30, 0).unwrap();
-        assert_eq!(dt, correct_dt);
-    }
-
-    #[test]
-    fn test_datetime_parser_valid_with_t_and_bracketed() {
-        // OFX datetime with T and bracketed timezone info
-        let dt = parse_ofx_datetime("20250725T143000[+7:PDT]").unwrap();
-        let correct_dt = Utc.with_ymd_and_hms(2025, 7, 25, 14, 30, 0).unwrap();
-        assert_eq!(dt, correct_dt);
-    }
-
-    #[test]
-    fn test_datetime_parser_invalid() {
-        // Invalid format should return error
-        let dt = parse_ofx_datetime("invalid-date-string");
-        assert!(dt.is_err());
-    }
-}
-
-/// Module to test the Stmttrn type
-#[cfg(test)]
-mod stmttrn_tests {
+mod stmttrn_tests {
     use super::*;
     use chrono::TimeZone;
+    use chrono::Utc;
 
     #[test]
     fn test_stmttrn_parse_valid_minimal() {
@@ -787,19 +1476,65 @@ mod stmttrn_tests {
         </STMTTRN>";
         let mut tokens = tokenize(input);
         let stmttrn = Stmttrn::parse(&mut tokens).unwrap();
-        assert_eq!(stmttrn.trans_type, "DEBIT");
+        assert_eq!(stmttrn.trans_type, TransactionType::Debit);
         assert_eq!(
             stmttrn.dt_posted,
             Utc.with_ymd_and_hms(2025, 7, 25, 14, 30, 0).unwrap()
         );
-        assert_eq!(stmttrn.trans_amount, -100.51);
+        assert_eq!(stmttrn.trans_amount, Amount::new(-10051, 2));
         assert_eq!(stmttrn.fit_id, "12345");
-        assert_eq!(stmttrn.name, "Test Transaction");
+        assert_eq!(stmttrn.name, Some("Test Transaction".to_string()));
         assert!(stmttrn.memo.is_none());
         assert!(stmttrn.correct_fit_id.is_none());
         assert!(stmttrn.correct_action.is_none());
     }
 
+    #[test]
+    fn test_stmttrn_parse_valid_with_leaf_end_tags() {
+        let input = "\
+            <TRNTYPE>DEBIT</TRNTYPE>\
+            <DTPOSTED>20250725T143000Z</DTPOSTED>\
+            <TRNAMT>-100.51</TRNAMT>\
+            <FITID>12345\
+            <NAME>Test Transaction</NAME>\
+            <MEMO>Test Memo</MEMO>\
+        </STMTTRN>";
+        let mut tokens = tokenize(input);
+        let stmttrn = Stmttrn::parse(&mut tokens).unwrap();
+        assert_eq!(stmttrn.trans_type, TransactionType::Debit);
+        assert_eq!(stmttrn.trans_amount, Amount::new(-10051, 2));
+        assert_eq!(stmttrn.fit_id, "12345");
+        assert_eq!(stmttrn.name, Some("Test Transaction".to_string()));
+        assert_eq!(stmttrn.memo, Some("Test Memo".to_string()));
+    }
+
+    #[test]
+    fn test_stmttrn_parse_mismatched_leaf_end_tag() {
+        let input = "\
+            <TRNTYPE>DEBIT\
+            <NAME>Test Transaction</MEMO>\
+        </STMTTRN>";
+        let mut tokens = tokenize(input);
+        let stmttrn = Stmttrn::parse(&mut tokens);
+        assert!(
+            matches!(stmttrn, Err(QFXParsingError::UnexpectedToken { message: msg, .. }) if msg.contains("/MEMO"))
+        );
+    }
+
+    #[test]
+    fn test_stmttrn_parse_stray_bracket_in_value() {
+        let input = "\
+            <TRNTYPE>DEBIT\
+            <NAME>B<3 CAFE\
+        </STMTTRN>";
+        let mut tokens = tokenize(input);
+        let stmttrn = Stmttrn::parse(&mut tokens);
+        assert!(matches!(
+            stmttrn,
+            Err(QFXParsingError::InvalidSyntax { .. })
+        ));
+    }
+
     #[test]
     fn test_stmttrn_parse_invalid_transaction() {
         let input = "\
@@ -815,13 +1550,50 @@ mod stmttrn_tests {
         assert!(stmttrn.is_err());
         if let Err(e) = stmttrn {
             assert!(
-                matches!(e, QFXParsingError::InvalidTransactionAmount(_)),
+                matches!(e, QFXParsingError::InvalidTransactionAmount { .. }),
                 "Expected InvalidTransactionAmount error, got: {:?}",
                 e
             );
         }
     }
 
+    #[test]
+    fn test_stmttrn_parse_date_only() {
+        let input = "\
+            <TRNTYPE>DEBIT\
+            <DTPOSTED>20250725\
+            <TRNAMT>-100.51\
+            <FITID>12345\
+            <NAME>Test Transaction\
+        </STMTTRN>";
+        let mut tokens = tokenize(input);
+        let stmttrn = Stmttrn::parse(&mut tokens).unwrap();
+        assert_eq!(stmttrn.dt_posted.precision(), DateTimePrecision::Day);
+        assert_eq!(
+            stmttrn.dt_posted.date(),
+            chrono::NaiveDate::from_ymd_opt(2025, 7, 25).unwrap()
+        );
+        assert!(stmttrn.to_string().contains("dt_posted: 2025-07-25,"));
+    }
+
+    #[test]
+    fn test_stmttrn_parse_non_decimal_amounts() {
+        for amount in ["1e3", "NaN", "inf", "-Infinity", "0x10"] {
+            let input = format!(
+                "<TRNTYPE>DEBIT<DTPOSTED>20250725T143000Z<TRNAMT>{}<FITID>1<NAME>A</STMTTRN>",
+                amount
+            );
+            let mut tokens = tokenize(&input);
+            let stmttrn = Stmttrn::parse(&mut tokens);
+            assert!(
+                matches!(&stmttrn, Err(QFXParsingError::InvalidTransactionAmount { value, .. }) if value == amount),
+                "Expected {} to be rejected, got: {:?}",
+                amount,
+                stmttrn
+            );
+        }
+    }
+
     #[test]
     fn test_stmttrn_parse_missing_transaction() {
         let input = "\
@@ -835,7 +1607,7 @@ mod stmttrn_tests {
         assert!(stmttrn.is_err());
         if let Err(e) = stmttrn {
             assert!(
-                matches!(e, QFXParsingError::MissingRequiredValue(_)),
+                matches!(e, QFXParsingError::MissingRequiredValue { .. }),
                 "Expected MissingRequiredValue error, got: {:?}",
                 e
             );
@@ -855,7 +1627,7 @@ mod stmttrn_tests {
         assert!(stmttrn.is_err());
         if let Err(e) = stmttrn {
             assert!(
-                matches!(e, QFXParsingError::MissingRequiredValue(_)),
+                matches!(e, QFXParsingError::MissingRequiredValue { .. }),
                 "Expected MissingRequiredValue error, got: {:?}",
                 e
             );
@@ -875,13 +1647,105 @@ mod stmttrn_tests {
         assert!(stmttrn.is_err());
         if let Err(e) = stmttrn {
             assert!(
-                matches!(e, QFXParsingError::MissingRequiredValue(_)),
+                matches!(e, QFXParsingError::MissingRequiredValue { .. }),
                 "Expected MissingRequiredValue error, got: {:?}",
                 e
             );
         }
     }
 
+    #[test]
+    fn test_stmttrn_parse_all_elements() {
+        let input = "\
+            <TRNTYPE>PAYMENT\
+            <DTPOSTED>20250725\
+            <DTUSER>20250723120000\
+            <DTAVAIL>20250726\
+            <TRNAMT>-42.10\
+            <FITID>12345\
+            <SRVRTID>SRV-1\
+            <REFNUM>REF-77\
+            <PAYEEID>P100\
+            <SIC>5411\
+            <PAYEE>\
+                <NAME>Corner Grocer\
+                <ADDR1>1 Main St\
+                <ADDR2>Unit 4\
+                <CITY>Springfield\
+                <STATE>IL\
+                <POSTALCODE>62701\
+                <COUNTRY>USA\
+                <PHONE>555-0100\
+            </PAYEE>\
+            <EXTDNAME>Corner Grocer Springfield\
+            <MEMO>Groceries\
+        </STMTTRN>";
+        let mut tokens = tokenize(input);
+        let stmttrn = Stmttrn::parse(&mut tokens).unwrap();
+        assert_eq!(
+            stmttrn.dt_user.as_ref().unwrap(),
+            &Utc.with_ymd_and_hms(2025, 7, 23, 12, 0, 0).unwrap()
+        );
+        assert_eq!(
+            stmttrn.dt_avail.as_ref().unwrap().date(),
+            chrono::NaiveDate::from_ymd_opt(2025, 7, 26).unwrap()
+        );
+        assert_eq!(stmttrn.srvr_tid, Some("SRV-1".to_string()));
+        assert_eq!(stmttrn.ref_num, Some("REF-77".to_string()));
+        assert_eq!(stmttrn.payee_id, Some("P100".to_string()));
+        assert_eq!(stmttrn.sic, Some("5411".to_string()));
+        assert_eq!(
+            stmttrn.ext_name,
+            Some("Corner Grocer Springfield".to_string())
+        );
+        assert!(stmttrn.name.is_none());
+        assert_eq!(stmttrn.payee_name(), "Corner Grocer");
+
+        let payee = stmttrn.payee.unwrap();
+        assert_eq!(payee.addr1, Some("1 Main St".to_string()));
+        assert_eq!(payee.addr2, Some("Unit 4".to_string()));
+        assert!(payee.addr3.is_none());
+        assert_eq!(payee.city, Some("Springfield".to_string()));
+        assert_eq!(payee.state, Some("IL".to_string()));
+        assert_eq!(payee.postal_code, Some("62701".to_string()));
+        assert_eq!(payee.country, Some("USA".to_string()));
+        assert_eq!(payee.phone, Some("555-0100".to_string()));
+    }
+
+    #[test]
+    fn test_stmttrn_parse_missing_name_and_payee() {
+        let input = "\
+            <TRNTYPE>DEBIT\
+            <DTPOSTED>20250725T143000Z\
+            <TRNAMT>-1.00\
+            <FITID>12345\
+            <EXTDNAME>Only an extended name\
+        </STMTTRN>";
+        let mut tokens = tokenize(input);
+        let stmttrn = Stmttrn::parse(&mut tokens);
+        assert!(matches!(
+            stmttrn,
+            Err(QFXParsingError::MissingRequiredValue { message, .. }) if message.contains("NAME or PAYEE")
+        ));
+    }
+
+    #[test]
+    fn test_stmttrn_parse_payee_missing_name() {
+        let input = "\
+            <TRNTYPE>DEBIT\
+            <DTPOSTED>20250725T143000Z\
+            <TRNAMT>-1.00\
+            <FITID>12345\
+            <PAYEE><CITY>Springfield</PAYEE>\
+        </STMTTRN>";
+        let mut tokens = tokenize(input);
+        let stmttrn = Stmttrn::parse(&mut tokens);
+        assert!(matches!(
+            stmttrn,
+            Err(QFXParsingError::MissingRequiredValue { message, .. }) if message.contains("PAYEE")
+        ));
+    }
+
     #[test]
     fn test_stmttrn_parse_missing_fitid() {
         let input = "\
@@ -895,7 +1759,7 @@ mod stmttrn_tests {
         assert!(stmttrn.is_err());
         if let Err(e) = stmttrn {
             assert!(
-                matches!(e, QFXParsingError::MissingRequiredValue(_)),
+                matches!(e, QFXParsingError::MissingRequiredValue { .. }),
                 "Expected MissingRequiredValue error, got: {:?}",
                 e
             );
@@ -915,7 +1779,7 @@ mod stmttrn_tests {
         assert!(stmttrn.is_err());
         if let Err(e) = stmttrn {
             assert!(
-                matches!(e, QFXParsingError::MissingRequiredValue(_)),
+                matches!(e, QFXParsingError::MissingRequiredValue { .. }),
                 "Expected MissingRequiredValue error, got: {:?}",
                 e
             );
@@ -950,207 +1814,1124 @@ mod qfx_file_tests {
             "Expected at least one section to be present in parsed QFX"
         );
 
-        let bank_transactions = qfx
-            .bank_msg_srs_v1
+        let bank_transactions = &qfx.bank_msg_srs_v1.as_ref().unwrap().stmttrns[0]
+            .stmtrs
+            .as_ref()
+            .unwrap()
+            .banktranslist
+            .transactions;
+        assert_eq!(bank_transactions.len(), 2);
+
+        // First transaction
+        let t0 = &bank_transactions[0];
+        assert_eq!(t0.trans_type, TransactionType::Debit);
+        assert_eq!(
+            t0.dt_posted,
+            chrono::Utc.with_ymd_and_hms(2025, 7, 15, 8, 0, 0).unwrap()
+        );
+        assert_eq!(t0.trans_amount, Amount::new(-5575, 2));
+        assert_eq!(t0.fit_id, "TXN123456");
+        assert_eq!(t0.check_num, Some("1005".to_string()));
+        assert_eq!(t0.name, Some("GROCERY STORE".to_string()));
+        assert_eq!(t0.memo, Some("Weekly groceries".to_string()));
+
+        // Second transaction
+        let t1 = &bank_transactions[1];
+        assert_eq!(t1.trans_type, TransactionType::Credit);
+        assert_eq!(
+            t1.dt_posted,
+            chrono::Utc.with_ymd_and_hms(2025, 7, 16, 9, 0, 0).unwrap()
+        );
+        assert_eq!(t1.trans_amount, Amount::new(100000, 2));
+        assert_eq!(t1.fit_id, "TXN123457");
+        assert_eq!(t1.check_num, None);
+        assert_eq!(t1.name, Some("PAYROLL".to_string()));
+        assert_eq!(t1.memo, Some("DIRECT DEPOSIT".to_string()));
+
+        let cc_transactions = &qfx.credit_card_msg_srs_v1.as_ref().unwrap().ccstmttrns[0]
+            .ccstmtrs
+            .as_ref()
+            .unwrap()
+            .banktranslist
+            .transactions;
+        assert_eq!(cc_transactions.len(), 2);
+
+        // First transaction
+        let t0 = &cc_transactions[0];
+        assert_eq!(t0.trans_type, TransactionType::Debit);
+        assert_eq!(
+            t0.dt_posted,
+            chrono::Utc.with_ymd_and_hms(2025, 7, 15, 8, 0, 0).unwrap()
+        );
+        assert_eq!(t0.trans_amount, Amount::new(-5575, 2));
+        assert_eq!(t0.fit_id, "TXN123456");
+        assert_eq!(t0.check_num, None);
+        assert_eq!(t0.name, Some("CASH BACK".to_string()));
+        assert_eq!(t0.memo, Some("Weekly groceries".to_string()));
+
+        // Second transaction
+        let t1 = &bank_transactions[1];
+        assert_eq!(t1.trans_type, TransactionType::Credit);
+        assert_eq!(
+            t1.dt_posted,
+            chrono::Utc.with_ymd_and_hms(2025, 7, 16, 9, 0, 0).unwrap()
+        );
+        assert_eq!(t1.trans_amount, Amount::new(100000, 2));
+        assert_eq!(t1.fit_id, "TXN123457");
+        assert_eq!(t1.check_num, None);
+        assert_eq!(t1.name, Some("PAYROLL".to_string()));
+        assert_eq!(t1.memo, Some("DIRECT DEPOSIT".to_string()));
+    }
+
+    #[test]
+    fn test_qfx_parse_valid_file_no_transactions() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/sample_bank_msg_no_transactions.qfx"
+        );
+        let result = QFX::new_from_file(file_path);
+        assert!(
+            result.is_ok(),
+            "Expected QFX::new_from_file to succeed, got error: {:?}",
+            result.err()
+        );
+        let qfx = result.unwrap();
+        // Optionally, check that at least one of the main sections is present
+        assert!(
+            qfx.sign_on_msg_srs_v1.is_some()
+                || qfx.credit_card_msg_srs_v1.is_none()
+                || qfx.bank_msg_srs_v1.is_some(),
+            "Expected at least one section to be present in parsed QFX"
+        );
+
+        let bank_transactions = &qfx.bank_msg_srs_v1.as_ref().unwrap().stmttrns[0]
+            .stmtrs
+            .as_ref()
+            .unwrap()
+            .banktranslist
+            .transactions;
+        assert_eq!(bank_transactions.len(), 0);
+
+        let cc_transactions = &qfx.credit_card_msg_srs_v1.as_ref().unwrap().ccstmttrns[0]
+            .ccstmtrs
+            .as_ref()
+            .unwrap()
+            .banktranslist
+            .transactions;
+        assert_eq!(cc_transactions.len(), 0);
+    }
+
+    #[test]
+    fn test_qfx_parse_from_str_bytes_and_reader() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/sample_bank_msg_transactions.qfx"
+        );
+        let contents = std::fs::read_to_string(file_path).unwrap();
+
+        let from_str = QFX::from_str(&contents).unwrap();
+        let from_bytes = QFX::from_bytes(contents.as_bytes()).unwrap();
+        let from_reader = QFX::from_reader(contents.as_bytes()).unwrap();
+        let from_path = QFX::from_path(std::path::Path::new(file_path)).unwrap();
+        for qfx in [from_str, from_bytes, from_reader, from_path] {
+            assert_eq!(qfx.get_transactions().len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_qfx_parse_header() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/sample_bank_msg_transactions.qfx"
+        );
+        let header = QFX::new_from_file(file_path).unwrap().header.unwrap();
+        assert_eq!(header.version, 102);
+        assert_eq!(header.charset.as_deref(), Some("1252"));
+        assert_eq!(header.new_file_uid.as_deref(), Some("20250730001"));
+
+        let qfx = "<OFX></OFX>".parse::<QFX>().unwrap();
+        assert!(qfx.header.is_none());
+
+        let result = "OFXHEADER:100\nVERSION:102\nSECURITY:TYPE1\n<OFX></OFX>".parse::<QFX>();
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::UnsupportedHeaderValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_qfx_parse_valid_xml_file() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/sample_bank_msg_transactions_xml.qfx"
+        );
+        let result = QFX::new_from_file(file_path);
+        assert!(
+            result.is_ok(),
+            "Expected QFX::new_from_file to succeed, got error: {:?}",
+            result.err()
+        );
+        let qfx = result.unwrap();
+        assert_eq!(qfx.header.as_ref().unwrap().flavour(), OfxFlavour::Xml);
+        assert_eq!(
+            qfx.sign_on_msg_srs_v1.as_ref().unwrap().sonrs.fi.org,
+            "BankOfExample"
+        );
+
+        let transactions = qfx.get_transactions();
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(transactions[0].name, "GROCERY STORE");
+        assert_eq!(transactions[0].check_num, "1005");
+        assert_eq!(transactions[0].account_id, "987654321");
+        assert_eq!(transactions[1].memo, "DIRECT DEPOSIT");
+        assert_eq!(transactions[2].account_id, "4111222233334444");
+    }
+
+    #[test]
+    fn test_qfx_parse_xml_file_text() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/sample_bank_msg_transactions_xml.qfx"
+        );
+        let contents = std::fs::read_to_string(file_path).unwrap();
+        let contents = contents
+            .replace(
+                "<NAME>GROCERY STORE</NAME>",
+                "<NAME><![CDATA[A & B <GROCERY>]]></NAME>",
+            )
+            .replace("<MEMO>Weekly groceries</MEMO>", "<MEMO>Spent > $50</MEMO>");
+        let transactions = contents.parse::<QFX>().unwrap().get_transactions();
+        assert_eq!(transactions[0].name, "A & B <GROCERY>");
+        assert_eq!(transactions[0].memo, "Spent > $50");
+
+        // SGML documents still reject a '>' that is not part of a tag
+        let sgml = "<OFX><SIGNONMSGSRSV1><SONRS><LANGUAGE>A > B</SONRS></SIGNONMSGSRSV1></OFX>";
+        assert!(matches!(
+            sgml.parse::<QFX>(),
+            Err(QFXParsingError::InvalidSyntax { .. })
+        ));
+    }
+
+    #[test]
+    fn test_qfx_parse_from_bytes_windows_1252() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/sample_bank_msg_transactions.qfx"
+        );
+        // The sample file declares CHARSET:1252
+        let mut contents = std::fs::read(file_path).unwrap();
+        let name_index = contents
+            .windows(13)
+            .position(|x| x == b"GROCERY STORE")
+            .unwrap();
+        contents.splice(name_index..name_index + 7, b"CAF\xC9 \x92".iter().copied());
+        let qfx = QFX::from_bytes(&contents).unwrap();
+        assert_eq!(qfx.get_transactions()[0].name, "CAFÉ ’ STORE");
+
+        let result = QFX::from_bytes_with_options(
+            &contents,
+            &ParseOptions {
+                encoding: Some(Encoding::Utf8),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::InvalidEncoding { .. })
+        ));
+    }
+
+    #[test]
+    fn test_qfx_parse_from_str_with_bom() {
+        let qfx = "\u{feff}OFXHEADER:100\nVERSION:102\n<OFX></OFX>".parse::<QFX>();
+        assert!(qfx.is_ok());
+    }
+
+    #[test]
+    fn test_qfx_parse_from_str_missing_ofx_tag() {
+        let result = "OFXHEADER:100\n<BANKMSGSRSV1>".parse::<QFX>();
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::UnexpectedToken { .. })
+        ));
+    }
+
+    #[test]
+    fn test_qfx_parse_missing_file() {
+        let result = QFX::new_from_file("tests/data/does_not_exist.qfx");
+        assert!(
+            result.is_err(),
+            "Expected error when parsing non-existent file"
+        );
+    }
+
+    #[test]
+    fn test_qfx_get_transactions() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/sample_bank_msg_transactions.qfx"
+        );
+        let result = QFX::new_from_file(file_path);
+        assert!(
+            result.is_ok(),
+            "Expected QFX::new_from_file to succeed, got error: {:?}",
+            result.err()
+        );
+        let qfx = result.unwrap();
+
+        // Test the get_transactions function
+        let transactions = qfx.get_transactions();
+
+        // Should have 4 total transactions (2 bank + 2 credit card)
+        assert_eq!(transactions.len(), 4);
+
+        // Test first bank transaction
+        let bank_txn_1 = transactions
+            .iter()
+            .find(|t| t.fit_id == "TXN123456" && t.account_type != AccountType::CreditCard)
+            .unwrap();
+        assert_eq!(bank_txn_1.trans_type, TransactionType::Debit);
+        assert_eq!(bank_txn_1.trans_amount, Amount::new(-5575, 2));
+        assert_eq!(bank_txn_1.name, "GROCERY STORE");
+        assert_eq!(bank_txn_1.memo, "Weekly groceries");
+        assert_eq!(bank_txn_1.check_num, "1005");
+        assert_eq!(bank_txn_1.account_id, "987654321");
+        assert_eq!(bank_txn_1.account_type, AccountType::Checking);
+        assert_eq!(bank_txn_1.bank_id, "123456789");
+
+        // Test second bank transaction
+        let bank_txn_2 = transactions
+            .iter()
+            .find(|t| t.fit_id == "TXN123457" && t.account_type != AccountType::CreditCard)
+            .unwrap();
+        assert_eq!(bank_txn_2.trans_type, TransactionType::Credit);
+        assert_eq!(bank_txn_2.trans_amount, Amount::new(100000, 2));
+        assert_eq!(bank_txn_2.name, "PAYROLL");
+        assert_eq!(bank_txn_2.memo, "DIRECT DEPOSIT");
+        assert_eq!(bank_txn_2.check_num, "");
+        assert_eq!(bank_txn_2.account_id, "987654321");
+        assert_eq!(bank_txn_2.account_type, AccountType::Checking);
+
+        // Test first credit card transaction
+        let cc_txn_1 = transactions
+            .iter()
+            .find(|t| t.fit_id == "TXN123456" && t.account_type == AccountType::CreditCard)
+            .unwrap();
+        assert_eq!(cc_txn_1.trans_type, TransactionType::Debit);
+        assert_eq!(cc_txn_1.trans_amount, Amount::new(-5575, 2));
+        assert_eq!(cc_txn_1.name, "CASH BACK");
+        assert_eq!(cc_txn_1.memo, "Weekly groceries");
+        assert_eq!(cc_txn_1.check_num, "");
+        assert_eq!(cc_txn_1.account_id, "4111222233334444");
+        assert_eq!(cc_txn_1.account_type, AccountType::CreditCard);
+        assert_eq!(cc_txn_1.bank_id, "");
+
+        // Test second credit card transaction
+        let cc_txn_2 = transactions
+            .iter()
+            .find(|t| t.fit_id == "TXN123457" && t.account_type == AccountType::CreditCard)
+            .unwrap();
+        assert_eq!(cc_txn_2.trans_type, TransactionType::Credit);
+        assert_eq!(cc_txn_2.trans_amount, Amount::new(100000, 2));
+        assert_eq!(cc_txn_2.name, "PAYROLL");
+        assert_eq!(cc_txn_2.memo, "DIRECT DEPOSIT");
+        assert_eq!(cc_txn_2.check_num, "");
+        assert_eq!(cc_txn_2.account_id, "4111222233334444");
+        assert_eq!(cc_txn_2.account_type, AccountType::CreditCard);
+
+        // Verify all transactions have account information
+        for transaction in &transactions {
+            assert!(
+                !transaction.account_id.is_empty(),
+                "Account ID should not be empty"
+            );
+            assert!(!transaction.fit_id.is_empty(), "FIT ID should not be empty");
+            assert!(!transaction.name.is_empty(), "Name should not be empty");
+        }
+    }
+
+    #[test]
+    fn test_qfx_get_transactions_multiple_accounts() {
+        let file_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/sample_bank_msg_multiple_accounts.qfx"
+        );
+        let qfx = QFX::new_from_file(file_path).unwrap();
+        assert_eq!(qfx.bank_msg_srs_v1.as_ref().unwrap().stmttrns.len(), 2);
+        let sonrs = &qfx.sign_on_msg_srs_v1.as_ref().unwrap().sonrs;
+        assert!(sonrs.status.as_ref().unwrap().is_success());
+        for stmttrnrs in &qfx.bank_msg_srs_v1.as_ref().unwrap().stmttrns {
+            assert!(stmttrnrs.status.as_ref().unwrap().is_success());
+        }
+
+        let transactions = qfx.get_transactions();
+        assert_eq!(transactions.len(), 5);
+
+        let checking: Vec<&Transaction> = transactions
+            .iter()
+            .filter(|t| t.account_id == "987654321")
+            .collect();
+        assert_eq!(checking.len(), 2);
+        assert!(
+            checking
+                .iter()
+                .all(|t| t.account_type == AccountType::Checking)
+        );
+
+        let savings: Vec<&Transaction> = transactions
+            .iter()
+            .filter(|t| t.account_id == "987654322")
+            .collect();
+        assert_eq!(savings.len(), 1);
+        assert_eq!(savings[0].account_type, AccountType::Savings);
+        assert_eq!(savings[0].fit_id, "TXN223457");
+        assert_eq!(savings[0].trans_type, TransactionType::Xfer);
+        assert_eq!(savings[0].trans_amount, Amount::new(20000, 2));
+
+        assert_eq!(
+            qfx.credit_card_msg_srs_v1
+                .as_ref()
+                .unwrap()
+                .ccstmttrns
+                .len(),
+            2
+        );
+        let cards: Vec<(&str, &str)> = transactions
+            .iter()
+            .filter(|t| t.account_type == AccountType::CreditCard)
+            .map(|t| (t.account_id.as_str(), t.fit_id.as_str()))
+            .collect();
+        assert_eq!(
+            cards,
+            vec![
+                ("4111111111111111", "CC100001"),
+                ("5500000000000004", "CC200001")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_qfx_duplicate_message_sets() {
+        let sign_on = "<SIGNONMSGSRSV1><SONRS><STATUS><CODE>0<SEVERITY>INFO</STATUS>\
+            <DTSERVER>20250730080000<LANGUAGE>ENG<FI><ORG>Bank<FID>1</FI></SONRS></SIGNONMSGSRSV1>";
+        let statement = "<BANKTRANLIST><DTSTART>20250701000000<DTEND>20250730235959</BANKTRANLIST>";
+        let bank = format!(
+            "<BANKMSGSRSV1><STMTTRNRS><STMTRS><BANKACCTFROM><ACCTID>1<ACCTTYPE>CHECKING\
+            </BANKACCTFROM>{statement}</STMTRS></STMTTRNRS></BANKMSGSRSV1>"
+        );
+        let credit_card = format!(
+            "<CREDITCARDMSGSRSV1><CCSTMTTRNRS><CCSTMTRS><CCACCTFROM><ACCTID>1</CCACCTFROM>\
+            {statement}</CCSTMTRS></CCSTMTTRNRS></CREDITCARDMSGSRSV1>"
+        );
+        for (tag, message_set) in [
+            ("SIGNONMSGSRSV1", sign_on.to_string()),
+            ("BANKMSGSRSV1", bank),
+            ("CREDITCARDMSGSRSV1", credit_card),
+        ] {
+            assert!(format!("<OFX>{message_set}</OFX>").parse::<QFX>().is_ok());
+
+            let result = format!("<OFX>{message_set}{message_set}</OFX>").parse::<QFX>();
+            assert!(
+                matches!(
+                    &result,
+                    Err(QFXParsingError::UnexpectedToken { message: msg, found: Some(found), .. })
+                        if msg.contains("is already set") && *found == tag
+                ),
+                "Expected {} to be rejected when repeated, got {:?}",
+                tag,
+                result.err()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod error_context_tests {
+    use super::*;
+
+    fn statement(transactions: &[&str]) -> String {
+        let transactions: Vec<String> = transactions
+            .iter()
+            .map(|x| format!("<STMTTRN>\n{}\n</STMTTRN>\n", x))
+            .collect();
+        format!(
+            "OFXHEADER:100\nVERSION:102\n\n<OFX>\n<BANKMSGSRSV1>\n<STMTTRNRS>\n<STMTRS>\n\
+            <BANKTRANLIST>\n<DTSTART>20250701120000\n<DTEND>20250731120000\n{}</BANKTRANLIST>\n\
+            </STMTRS>\n</STMTTRNRS>\n</BANKMSGSRSV1>\n</OFX>",
+            transactions.concat()
+        )
+    }
+
+    const VALID: &str =
+        "<TRNTYPE>DEBIT\n<DTPOSTED>20250725120000\n<TRNAMT>-1.00\n<FITID>1\n<NAME>A";
+
+    #[test]
+    fn test_error_context_invalid_amount() {
+        let input = statement(&[
+            VALID,
+            VALID,
+            "<TRNTYPE>DEBIT\n<DTPOSTED>20250725120000\n<TRNAMT>  12x.00\n<FITID>1\n<NAME>A",
+        ]);
+        let error = input.parse::<QFX>().err().unwrap();
+        let QFXParsingError::InvalidTransactionAmount { value, context, .. } = &error else {
+            panic!("Expected InvalidTransactionAmount, got: {:?}", error);
+        };
+        assert_eq!(value, "12x.00");
+        assert_eq!(
+            context.path,
+            "OFX/BANKMSGSRSV1/STMTTRNRS[0]/STMTRS/BANKTRANLIST/STMTTRN[2]/TRNAMT"
+        );
+        let position = context.position.unwrap();
+        assert_eq!((position.line, position.column), (28, 11));
+        assert_eq!(&input[position.offset..position.offset + 6], "12x.00");
+        assert!(
+            error
+                .to_string()
+                .contains("Invalid transaction amount 12x.00")
+        );
+        assert!(error.to_string().contains("line 28, column 11"));
+    }
+
+    #[test]
+    fn test_error_context_missing_value() {
+        let input = statement(&[
+            VALID,
+            "<TRNTYPE>DEBIT\n<DTPOSTED>20250725120000\n<TRNAMT>1\n<FITID>1",
+        ]);
+        let error = input.parse::<QFX>().err().unwrap();
+        let QFXParsingError::MissingRequiredValue { field, context, .. } = &error else {
+            panic!("Expected MissingRequiredValue, got: {:?}", error);
+        };
+        assert_eq!(field, "NAME");
+        assert_eq!(
+            context.path,
+            "OFX/BANKMSGSRSV1/STMTTRNRS[0]/STMTRS/BANKTRANLIST/STMTTRN[1]"
+        );
+        assert_eq!(context.position.unwrap().line, 23);
+    }
+
+    #[test]
+    fn test_error_context_unexpected_token() {
+        let input = statement(&[&format!("{}\n<FOO>BAR", VALID)]);
+        let error = input.parse::<QFX>().err().unwrap();
+        let QFXParsingError::UnexpectedToken {
+            expected,
+            found,
+            context,
+            ..
+        } = &error
+        else {
+            panic!("Expected UnexpectedToken, got: {:?}", error);
+        };
+        assert_eq!(found.as_deref(), Some("FOO"));
+        assert!(context.path.ends_with("/STMTTRN[0]/FOO"));
+        let expected = expected.as_deref().unwrap();
+        assert!(expected.starts_with("one of TRNTYPE, DTPOSTED, TRNAMT,"));
+        assert!(expected.ends_with(", ORIGCURRENCY or /STMTTRN"));
+    }
+
+    #[test]
+    fn test_error_context_unexpected_token_in_shared_aggregate() {
+        let input = "<BANKID>1<FOO></BANKACCTTO>";
+        let mut tokens = tokenize(input);
+        let error = Bankacctfrom::parse_aggregate(&mut tokens, "BANKACCTTO")
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            QFXParsingError::UnexpectedToken { expected: Some(expected), .. }
+                if expected == "one of BANKID, ACCTID, ACCTTYPE or /BANKACCTTO"
+        ));
+    }
+
+    #[test]
+    fn test_error_context_unexpected_eof() {
+        let input = "<OFX>\n<SIGNONMSGSRSV1>\n<SONRS>\n<DTSERVER>";
+        let error = input.parse::<QFX>().err().unwrap();
+        let QFXParsingError::UnexpectedEOF {
+            expected, context, ..
+        } = &error
+        else {
+            panic!("Expected UnexpectedEOF, got: {:?}", error);
+        };
+        assert_eq!(expected, "a value for DTSERVER");
+        assert_eq!(context.path, "OFX/SIGNONMSGSRSV1/SONRS/DTSERVER");
+        assert_eq!(context.position.unwrap().line, 4);
+    }
+
+    #[test]
+    fn test_error_context_eof_inside_stmttrn() {
+        let contents =
+            std::fs::read_to_string("tests/data/sample_bank_msg_transactions.qfx").unwrap();
+        let end = contents.find("</STMTTRN>").unwrap();
+        let error = contents[..end].parse::<QFX>().err().unwrap();
+        let QFXParsingError::UnexpectedEOF {
+            expected, context, ..
+        } = &error
+        else {
+            panic!("Expected UnexpectedEOF, got: {:?}", error);
+        };
+        assert_eq!(expected, "/STMTTRN");
+        assert!(context.path.ends_with("/BANKTRANLIST/STMTTRN[0]"));
+    }
+
+    #[test]
+    fn test_error_context_not_set_for_file_errors() {
+        let error = QFX::from_path("tests/data/does_not_exist.qfx")
+            .err()
+            .unwrap();
+        assert!(matches!(error, QFXParsingError::FileNotFound { .. }));
+        assert!(error.context().is_none());
+        assert!(!error.message().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod lenient_tests {
+    use super::*;
+
+    const INPUT: &str = "<OFX>
+<BANKMSGSRSV1>
+<STMTTRNRS>
+<STMTRS>
+<INTU.AGG>
+<INTU.A>1
+<INTU.AGG><INTU.B>2</INTU.B></INTU.AGG>
+</INTU.AGG>
+<BANKACCTFROM>
+<ACCTID>1234
+<ACCTTYPE>CHECKING
+</BANKACCTFROM>
+<BANKTRANLIST>
+<DTSTART>20250701120000
+<DTEND>20250731120000
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20250725120000
+<TRNAMT>-1.00
+<INTU.XYZ>Foo
+<FITID>1
+<NAME>A
+</MEMO>
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>CREDIT
+<DTPOSTED>20250726120000
+<TRNAMT>2.00
+<FITID>2
+<INTU.EMPTY></INTU.EMPTY>
+<NAME>B
+</STMTTRN>
+</BANKTRANLIST>
+</STMTRS>
+</STMTTRNRS>
+</BANKMSGSRSV1>
+</OFX>";
+
+    fn lenient() -> ParseOptions {
+        ParseOptions {
+            strict: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_lenient_skips_unknown_tags() {
+        assert!(matches!(
+            INPUT.parse::<QFX>(),
+            Err(QFXParsingError::UnexpectedToken { found: Some(found), .. }) if found == "INTU.AGG"
+        ));
+
+        let (qfx, warnings) = QFX::from_str_with_options(INPUT, &lenient()).unwrap();
+        let transactions = qfx.get_transactions();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].name, "A");
+        assert_eq!(transactions[0].fit_id, "1");
+        assert_eq!(transactions[1].name, "B");
+
+        let tags: Vec<&str> = warnings.iter().map(|x| x.tag.as_str()).collect();
+        assert_eq!(tags, vec!["INTU.AGG", "INTU.XYZ", "/MEMO", "INTU.EMPTY"]);
+        assert_eq!(
+            warnings[1].context.path,
+            "OFX/BANKMSGSRSV1/STMTTRNRS[0]/STMTRS/BANKTRANLIST/STMTTRN[0]/INTU.XYZ"
+        );
+        assert_eq!(warnings[1].context.position.unwrap().line, 20);
+        assert!(warnings[1].message.contains("STMTTRN"));
+        assert!(warnings[1].to_string().starts_with("Skipped INTU.XYZ."));
+    }
+
+    #[test]
+    fn test_lenient_unknown_leaf_without_value() {
+        let input = INPUT
+            .replace("<NAME>A", "<INTU.FLAG>\n<NAME>A")
+            .replace("<INTU.EMPTY></INTU.EMPTY>", "<INTU.EMPTY>");
+        let (qfx, warnings) = QFX::from_str_with_options(&input, &lenient()).unwrap();
+        let transactions = qfx.get_transactions();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].name, "A");
+        assert_eq!(transactions[1].name, "B");
+
+        let tags: Vec<&str> = warnings.iter().map(|x| x.tag.as_str()).collect();
+        assert_eq!(
+            tags,
+            vec!["INTU.AGG", "INTU.XYZ", "INTU.FLAG", "/MEMO", "INTU.EMPTY"]
+        );
+        assert!(warnings[2].context.path.ends_with("/STMTTRN[0]/INTU.FLAG"));
+    }
+
+    #[test]
+    fn test_lenient_unknown_element_without_end_tag() {
+        // INTU.AGG is never closed, so it is taken to be a leaf without a value instead of
+        // reading to the end of the file looking for its end tag
+        let input = "<OFX><BANKMSGSRSV1><INTU.AGG><INTU.A>1</BANKMSGSRSV1></OFX>";
+        let result = QFX::from_str_with_options(input, &lenient());
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::MissingRequiredValue { field, .. }) if field == "STMTTRNRS"
+        ));
+    }
+
+    #[test]
+    fn test_lenient_unknown_transaction_type() {
+        let input = "<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<BANKACCTFROM><ACCTID>1234<ACCTTYPE>CHECKING</BANKACCTFROM>
+<BANKTRANLIST><DTSTART>20250701<DTEND>20250731
+<STMTTRN><TRNTYPE>OTHER<DTPOSTED>20250725<TRNAMT>-1.00<FITID>1<NAME>A</STMTTRN>
+<STMTTRN><TRNTYPE>ACH<DTPOSTED>20250726<TRNAMT>-2.00<FITID>2<NAME>B</STMTTRN>
+</BANKTRANLIST>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>";
+        // Types that are not in the spec are kept in every mode
+        let (qfx, warnings) = QFX::from_str_with_options(input, &ParseOptions::default()).unwrap();
+        let transactions = qfx.get_transactions();
+        assert_eq!(transactions[0].trans_type, TransactionType::OtherType);
+        assert_eq!(
+            transactions[1].trans_type,
+            TransactionType::Other("ACH".to_string())
+        );
+        assert!(warnings.is_empty());
+
+        let (qfx, warnings) = QFX::from_str_with_options(input, &lenient()).unwrap();
+        assert_eq!(
+            qfx.get_transactions()[1].trans_type,
+            TransactionType::Other("ACH".to_string())
+        );
+        assert_eq!(warnings.len(), 1);
+        let warning = &warnings[0];
+        assert_eq!(warning.tag, "TRNTYPE");
+        assert!(!warning.skipped);
+        assert!(warning.context.path.ends_with("/STMTTRN[1]/TRNTYPE"));
+        assert!(
+            warning
+                .to_string()
+                .starts_with("Kept the invalid value of TRNTYPE. ACH is not")
+        );
+    }
+
+    #[test]
+    fn test_lenient_from_bytes() {
+        let (qfx, warnings) = QFX::from_bytes_with_options(INPUT.as_bytes(), &lenient()).unwrap();
+        assert_eq!(qfx.get_transactions().len(), 2);
+        assert_eq!(warnings.len(), 4);
+
+        let (_, warnings) =
+            QFX::from_bytes_with_options(b"<OFX></OFX>", &ParseOptions::default()).unwrap();
+        assert!(warnings.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod payee_tests {
+    use super::*;
+
+    #[test]
+    fn test_get_transactions_uses_payee() {
+        let input = "<OFX>
+<CREDITCARDMSGSRSV1>
+<CCSTMTTRNRS>
+<TRNUID>1
+<CCSTMTRS>
+<CURDEF>USD
+<CCACCTFROM><ACCTID>4111</CCACCTFROM>
+<BANKTRANLIST>
+<DTSTART>20250701
+<DTEND>20250731
+<STMTTRN>
+<TRNTYPE>DEBIT<DTPOSTED>20250710<DTUSER>20250709<TRNAMT>-9.99<FITID>1<REFNUM>R1
+<PAYEE><NAME>Streaming Co<CITY>Los Gatos<STATE>CA</PAYEE>
+</STMTTRN>
+</BANKTRANLIST>
+</CCSTMTRS>
+</CCSTMTTRNRS>
+</CREDITCARDMSGSRSV1>
+</OFX>";
+        let qfx = input.parse::<QFX>().unwrap();
+        let transactions = qfx.get_transactions();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].name, "Streaming Co");
+        assert_eq!(transactions[0].ref_num, "R1");
+        assert!(transactions[0].srvr_tid.is_empty());
+        assert!(transactions[0].dt_user.is_some());
+        assert!(transactions[0].dt_avail.is_none());
+        assert_eq!(
+            transactions[0].payee.as_ref().unwrap().state,
+            Some("CA".to_string())
+        );
+    }
+}
+
+#[cfg(test)]
+mod currency_tests {
+    use super::*;
+
+    const TRAVEL_CARD: &str = "<OFX>
+<CREDITCARDMSGSRSV1>
+<CCSTMTTRNRS>
+<TRNUID>1
+<CCSTMTRS>
+<CURDEF>USD
+<CCACCTFROM><ACCTID>4111</CCACCTFROM>
+<BANKTRANLIST>
+<DTSTART>20250701
+<DTEND>20250731
+<STMTTRN>
+<TRNTYPE>POS<DTPOSTED>20250710<TRNAMT>-20.00<FITID>1<NAME>CAFE PARIS
+<CURRENCY><CURRATE>1.0850<CURSYM>EUR</CURRENCY>
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>POS<DTPOSTED>20250711<TRNAMT>-100.00<FITID>2<NAME>HOTEL ROMA
+<ORIGCURRENCY><CURRATE>1.0850<CURSYM>EUR</ORIGCURRENCY>
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>POS<DTPOSTED>20250712<TRNAMT>-5.00<FITID>3<NAME>HOME
+</STMTTRN>
+</BANKTRANLIST>
+</CCSTMTRS>
+</CCSTMTTRNRS>
+</CREDITCARDMSGSRSV1>
+</OFX>";
+
+    #[test]
+    fn test_currency_parse() {
+        let mut tokens = tokenize("<CURRATE>0.75<CURSYM>CAD</CURRENCY>");
+        let currency = Currency::parse(&mut tokens).unwrap();
+        assert_eq!(currency.rate, Amount::new(75, 2));
+        assert_eq!(currency.symbol, "CAD");
+
+        let mut tokens = tokenize("<CURSYM>CAD</ORIGCURRENCY>");
+        let currency = Currency::parse_aggregate(&mut tokens, "ORIGCURRENCY");
+        assert!(matches!(
+            currency,
+            Err(QFXParsingError::MissingRequiredValue { message, .. }) if message == "CURRATE value is required in ORIGCURRENCY"
+        ));
+
+        let mut tokens = tokenize("<CURRATE>abc<CURSYM>CAD</CURRENCY>");
+        assert!(matches!(
+            Currency::parse(&mut tokens),
+            Err(QFXParsingError::InvalidTransactionAmount { .. })
+        ));
+    }
+
+    #[test]
+    fn test_stmttrn_currency_amounts() {
+        let qfx = TRAVEL_CARD.parse::<QFX>().unwrap();
+        let stmttrns = &qfx.credit_card_msg_srs_v1.as_ref().unwrap().ccstmttrns[0]
+            .ccstmtrs
+            .as_ref()
             .unwrap()
-            .stmttrns
-            .stmtrs
             .banktranslist
             .transactions;
-        assert_eq!(bank_transactions.len(), 2);
+        assert_eq!(stmttrns[0].currency.as_ref().unwrap().symbol, "EUR");
+        assert!(stmttrns[0].orig_currency.is_none());
+        assert_eq!(stmttrns[1].orig_currency.as_ref().unwrap().symbol, "EUR");
 
-        // First transaction
-        let t0 = &bank_transactions[0];
-        assert_eq!(t0.trans_type, "DEBIT");
+        let transactions = qfx.get_transactions();
+        // TRNAMT is in EUR
+        assert_eq!(transactions[0].currency, "USD");
+        assert_eq!(transactions[0].original_currency, "EUR");
         assert_eq!(
-            t0.dt_posted,
-            chrono::Utc.with_ymd_and_hms(2025, 7, 15, 8, 0, 0).unwrap()
+            transactions[0].original_amount.unwrap().to_string(),
+            "-20.00"
         );
-        assert_eq!(t0.trans_amount, -55.75);
-        assert_eq!(t0.fit_id, "TXN123456");
-        assert_eq!(t0.check_num, Some("1005".to_string()));
-        assert_eq!(t0.name, "GROCERY STORE");
-        assert_eq!(t0.memo, Some("Weekly groceries".to_string()));
-
-        // Second transaction
-        let t1 = &bank_transactions[1];
-        assert_eq!(t1.trans_type, "CREDIT");
         assert_eq!(
-            t1.dt_posted,
-            chrono::Utc.with_ymd_and_hms(2025, 7, 16, 9, 0, 0).unwrap()
+            transactions[0].statement_amount.unwrap().to_string(),
+            "-21.70"
         );
-        assert_eq!(t1.trans_amount, 1000.00);
-        assert_eq!(t1.fit_id, "TXN123457");
-        assert_eq!(t1.check_num, None);
-        assert_eq!(t1.name, "PAYROLL");
-        assert_eq!(t1.memo, Some("DIRECT DEPOSIT".to_string()));
-
-        let cc_transactions = qfx
-            .credit_card_msg_srs_v1
-            .unwrap()
-            .ccstmttrns
-            .ccstmtrs
-            .banktranslist
-            .transactions;
-        assert_eq!(cc_transactions.len(), 2);
+        assert_eq!(transactions[0].currency_rate, Some(Amount::new(10850, 4)));
 
-        // First transaction
-        let t0 = &cc_transactions[0];
-        assert_eq!(t0.trans_type, "DEBIT");
+        // TRNAMT is in USD
+        assert_eq!(transactions[1].original_currency, "EUR");
         assert_eq!(
-            t0.dt_posted,
-            chrono::Utc.with_ymd_and_hms(2025, 7, 15, 8, 0, 0).unwrap()
+            transactions[1].statement_amount.unwrap().to_string(),
+            "-100.00"
+        );
+        assert_eq!(
+            transactions[1].original_amount.unwrap().to_string(),
+            "-92.17"
         );
-        assert_eq!(t0.trans_amount, -55.75);
-        assert_eq!(t0.fit_id, "TXN123456");
-        assert_eq!(t0.check_num, None);
-        assert_eq!(t0.name, "CASH BACK");
-        assert_eq!(t0.memo, Some("Weekly groceries".to_string()));
 
-        // Second transaction
-        let t1 = &bank_transactions[1];
-        assert_eq!(t1.trans_type, "CREDIT");
+        assert_eq!(transactions[2].original_currency, "USD");
         assert_eq!(
-            t1.dt_posted,
-            chrono::Utc.with_ymd_and_hms(2025, 7, 16, 9, 0, 0).unwrap()
+            transactions[2].statement_amount,
+            Some(transactions[2].trans_amount)
         );
-        assert_eq!(t1.trans_amount, 1000.00);
-        assert_eq!(t1.fit_id, "TXN123457");
-        assert_eq!(t1.check_num, None);
-        assert_eq!(t1.name, "PAYROLL");
-        assert_eq!(t1.memo, Some("DIRECT DEPOSIT".to_string()));
+        assert_eq!(
+            transactions[2].original_amount,
+            Some(transactions[2].trans_amount)
+        );
+        assert!(transactions[2].currency_rate.is_none());
     }
 
     #[test]
-    fn test_qfx_parse_valid_file_no_transactions() {
-        let file_path = concat!(
-            env!("CARGO_MANIFEST_DIR"),
-            "/tests/data/sample_bank_msg_no_transactions.qfx"
+    fn test_zero_currency_rate_strict() {
+        let input = TRAVEL_CARD.replace(
+            "<CURRATE>1.0850<CURSYM>EUR</ORIGCURRENCY>",
+            "<CURRATE>0<CURSYM>EUR</ORIGCURRENCY>",
         );
-        let result = QFX::new_from_file(file_path);
-        assert!(
-            result.is_ok(),
-            "Expected QFX::new_from_file to succeed, got error: {:?}",
-            result.err()
+        // The transaction is kept without the converted amount in strict mode too
+        let (qfx, warnings) = QFX::from_str_with_options(&input, &ParseOptions::default()).unwrap();
+        assert!(warnings.is_empty());
+        let transactions = qfx.get_transactions();
+        assert_eq!(transactions.len(), 3);
+        assert!(transactions[1].original_amount.is_none());
+        assert_eq!(transactions[1].currency_rate, Some(Amount::ZERO));
+    }
+
+    #[test]
+    fn test_zero_currency_rate_lenient() {
+        let input = TRAVEL_CARD.replace(
+            "<CURRATE>1.0850<CURSYM>EUR</ORIGCURRENCY>",
+            "<CURRATE>0<CURSYM>EUR</ORIGCURRENCY>",
         );
-        let qfx = result.unwrap();
-        // Optionally, check that at least one of the main sections is present
+        let options = ParseOptions {
+            strict: false,
+            ..Default::default()
+        };
+        let (qfx, warnings) = QFX::from_str_with_options(&input, &options).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tag, "CURRATE");
+        assert!(!warnings[0].skipped);
         assert!(
-            qfx.sign_on_msg_srs_v1.is_some()
-                || qfx.credit_card_msg_srs_v1.is_none()
-                || qfx.bank_msg_srs_v1.is_some(),
-            "Expected at least one section to be present in parsed QFX"
+            warnings[0]
+                .to_string()
+                .starts_with("Kept the invalid value of CURRATE.")
         );
 
-        let bank_transactions = qfx
-            .bank_msg_srs_v1
-            .unwrap()
-            .stmttrns
-            .stmtrs
-            .banktranslist
-            .transactions;
-        assert_eq!(bank_transactions.len(), 0);
-
-        let cc_transactions = qfx
-            .credit_card_msg_srs_v1
-            .unwrap()
-            .ccstmttrns
-            .ccstmtrs
-            .banktranslist
-            .transactions;
-        assert_eq!(cc_transactions.len(), 0);
+        let transactions = qfx.get_transactions();
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(
+            transactions[1].statement_amount,
+            Some(transactions[1].trans_amount)
+        );
+        assert!(transactions[1].original_amount.is_none());
+        assert_eq!(transactions[1].currency_rate, Some(Amount::ZERO));
     }
+}
+
+#[cfg(test)]
+mod transfer_tests {
+    use super::*;
 
     #[test]
-    fn test_qfx_parse_missing_file() {
-        let result = QFX::new_from_file("tests/data/does_not_exist.qfx");
-        assert!(
-            result.is_err(),
-            "Expected error when parsing non-existent file"
+    fn test_stmttrn_parse_bankacctto() {
+        let input = "\
+            <TRNTYPE>XFER<DTPOSTED>20250710<TRNAMT>-500.00<FITID>1<NAME>TO SAVINGS\
+            <BANKACCTTO><BANKID>021000021<ACCTID>987654322<ACCTTYPE>SAVINGS</BANKACCTTO>\
+        </STMTTRN>";
+        let mut tokens = tokenize(input);
+        let stmttrn = Stmttrn::parse(&mut tokens).unwrap();
+        assert_eq!(
+            stmttrn.bankacctto,
+            Some(Bankacctfrom {
+                acct_id: "987654322".to_string(),
+                acct_type: AccountType::Savings,
+                bank_id: Some("021000021".to_string()),
+            })
         );
+        assert!(stmttrn.ccacctto.is_none());
     }
 
     #[test]
-    fn test_qfx_get_transactions() {
-        let file_path = concat!(
-            env!("CARGO_MANIFEST_DIR"),
-            "/tests/data/sample_bank_msg_transactions.qfx"
+    fn test_stmttrn_parse_ccacctto() {
+        let input = "\
+            <TRNTYPE>XFER<DTPOSTED>20250710<TRNAMT>-250.00<FITID>1<NAME>CARD PAYMENT\
+            <CCACCTTO><ACCTID>4111111111111111</CCACCTTO>\
+        </STMTTRN>";
+        let mut tokens = tokenize(input);
+        let stmttrn = Stmttrn::parse(&mut tokens).unwrap();
+        assert_eq!(stmttrn.ccacctto.unwrap().acct_id, "4111111111111111");
+        assert!(stmttrn.bankacctto.is_none());
+    }
+
+    #[test]
+    fn test_stmttrn_parse_bankacctto_missing_type() {
+        let input = "\
+            <TRNTYPE>XFER<DTPOSTED>20250710<TRNAMT>-500.00<FITID>1<NAME>TO SAVINGS\
+            <BANKACCTTO><ACCTID>987654322</BANKACCTTO>\
+        </STMTTRN>";
+        let mut tokens = tokenize(input);
+        assert!(matches!(
+            Stmttrn::parse(&mut tokens),
+            Err(QFXParsingError::MissingRequiredValue { message, .. }) if message == "ACCTTYPE is a required value in BANKACCTTO"
+        ));
+    }
+
+    #[test]
+    fn test_get_transactions_counterparty() {
+        let input = "<OFX>
+<BANKMSGSRSV1>
+<STMTTRNRS>
+<STMTRS>
+<CURDEF>USD
+<BANKACCTFROM><BANKID>021000021<ACCTID>987654321<ACCTTYPE>CHECKING</BANKACCTFROM>
+<BANKTRANLIST>
+<DTSTART>20250701
+<DTEND>20250731
+<STMTTRN>
+<TRNTYPE>XFER<DTPOSTED>20250710<TRNAMT>-500.00<FITID>1<NAME>TO SAVINGS
+<BANKACCTTO><BANKID>021000021<ACCTID>987654322<ACCTTYPE>SAVINGS</BANKACCTTO>
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>XFER<DTPOSTED>20250711<TRNAMT>-250.00<FITID>2<NAME>CARD PAYMENT
+<CCACCTTO><ACCTID>4111111111111111</CCACCTTO>
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>DEBIT<DTPOSTED>20250712<TRNAMT>-5.00<FITID>3<NAME>COFFEE
+</STMTTRN>
+</BANKTRANLIST>
+</STMTRS>
+</STMTTRNRS>
+</BANKMSGSRSV1>
+</OFX>";
+        let transactions = input.parse::<QFX>().unwrap().get_transactions();
+        assert_eq!(transactions[0].counterparty_account_id, "987654322");
+        assert_eq!(
+            transactions[0].counterparty_account_type,
+            Some(AccountType::Savings)
         );
-        let result = QFX::new_from_file(file_path);
-        assert!(
-            result.is_ok(),
-            "Expected QFX::new_from_file to succeed, got error: {:?}",
-            result.err()
+        assert_eq!(transactions[0].counterparty_bank_id, "021000021");
+
+        assert_eq!(transactions[1].counterparty_account_id, "4111111111111111");
+        assert_eq!(
+            transactions[1].counterparty_account_type,
+            Some(AccountType::CreditCard)
         );
-        let qfx = result.unwrap();
+        assert!(transactions[1].counterparty_bank_id.is_empty());
 
-        // Test the get_transactions function
-        let transactions = qfx.get_transactions();
+        assert!(transactions[2].counterparty_account_id.is_empty());
+        assert!(transactions[2].counterparty_account_type.is_none());
+    }
+}
 
-        // Should have 4 total transactions (2 bank + 2 credit card)
-        assert_eq!(transactions.len(), 4);
+#[cfg(test)]
+mod server_error_tests {
+    use super::*;
 
-        // Test first bank transaction
-        let bank_txn_1 = transactions
-            .iter()
-            .find(|t| t.fit_id == "TXN123456" && !t.account_type.is_empty())
-            .unwrap();
-        assert_eq!(bank_txn_1.trans_type, "DEBIT");
-        assert_eq!(bank_txn_1.trans_amount, -55.75);
-        assert_eq!(bank_txn_1.name, "GROCERY STORE");
-        assert_eq!(bank_txn_1.memo, "Weekly groceries");
-        assert_eq!(bank_txn_1.check_num, "1005");
-        assert_eq!(bank_txn_1.account_id, "987654321");
-        assert_eq!(bank_txn_1.account_type, "CHECKING");
+    const SIGNON_FAILED: &str = "<OFX>
+<SIGNONMSGSRSV1>
+<SONRS>
+<STATUS>
+<CODE>15500
+<SEVERITY>ERROR
+<MESSAGE>Invalid user ID or password
+</STATUS>
+<DTSERVER>20250730080000
+<FI><ORG>Bank<FID>1</FI>
+</SONRS>
+</SIGNONMSGSRSV1>
+</OFX>";
+
+    const STATEMENT_FAILED: &str = "<OFX>
+<BANKMSGSRSV1>
+<STMTTRNRS>
+<TRNUID>1
+<STATUS>
+<CODE>2003
+<SEVERITY>ERROR
+</STATUS>
+</STMTTRNRS>
+<STMTTRNRS>
+<TRNUID>2
+<STATUS>
+<CODE>0
+<SEVERITY>INFO
+</STATUS>
+<STMTRS>
+<BANKACCTFROM><ACCTID>2222<ACCTTYPE>SAVINGS</BANKACCTFROM>
+<BANKTRANLIST>
+<DTSTART>20250701
+<DTEND>20250731
+<STMTTRN><TRNTYPE>INT<DTPOSTED>20250731<TRNAMT>0.42<FITID>1<NAME>INTEREST</STMTTRN>
+</BANKTRANLIST>
+</STMTRS>
+</STMTTRNRS>
+</BANKMSGSRSV1>
+</OFX>";
+
+    fn server_errors() -> ParseOptions {
+        ParseOptions {
+            server_errors: true,
+            ..Default::default()
+        }
+    }
 
-        // Test second bank transaction
-        let bank_txn_2 = transactions
-            .iter()
-            .find(|t| t.fit_id == "TXN123457" && !t.account_type.is_empty())
-            .unwrap();
-        assert_eq!(bank_txn_2.trans_type, "CREDIT");
-        assert_eq!(bank_txn_2.trans_amount, 1000.00);
-        assert_eq!(bank_txn_2.name, "PAYROLL");
-        assert_eq!(bank_txn_2.memo, "DIRECT DEPOSIT");
-        assert_eq!(bank_txn_2.check_num, "");
-        assert_eq!(bank_txn_2.account_id, "987654321");
-        assert_eq!(bank_txn_2.account_type, "CHECKING");
+    #[test]
+    fn test_error_status_parses_by_default() {
+        let qfx = SIGNON_FAILED.parse::<QFX>().unwrap();
+        let status = qfx.sign_on_msg_srs_v1.unwrap().sonrs.status.unwrap();
+        assert_eq!(status.code, StatusCode::SignonInvalid);
+        assert_eq!(status.severity, Severity::Error);
+        assert!(!status.is_success());
+
+        let qfx = STATEMENT_FAILED.parse::<QFX>().unwrap();
+        let stmttrns = &qfx.bank_msg_srs_v1.as_ref().unwrap().stmttrns;
+        assert!(stmttrns[0].stmtrs.is_none());
+        assert!(stmttrns[1].stmtrs.is_some());
+        let transactions = qfx.get_transactions();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].account_id, "2222");
+    }
 
-        // Test first credit card transaction
-        let cc_txn_1 = transactions
-            .iter()
-            .find(|t| t.fit_id == "TXN123456" && t.account_type.is_empty())
+    #[test]
+    fn test_server_error_signon() {
+        let error = QFX::from_str_with_options(SIGNON_FAILED, &server_errors())
+            .err()
             .unwrap();
-        assert_eq!(cc_txn_1.trans_type, "DEBIT");
-        assert_eq!(cc_txn_1.trans_amount, -55.75);
-        assert_eq!(cc_txn_1.name, "CASH BACK");
-        assert_eq!(cc_txn_1.memo, "Weekly groceries");
-        assert_eq!(cc_txn_1.check_num, "");
-        assert_eq!(cc_txn_1.account_id, "4111222233334444");
-        assert_eq!(cc_txn_1.account_type, ""); // Credit card accounts have empty account_type
+        let QFXParsingError::ServerError {
+            code,
+            message,
+            context,
+        } = &error
+        else {
+            panic!("Expected ServerError, got: {:?}", error);
+        };
+        assert_eq!(*code, StatusCode::SignonInvalid);
+        assert_eq!(message, "Invalid user ID or password");
+        assert_eq!(context.path, "OFX/SIGNONMSGSRSV1/SONRS/STATUS");
+        assert_eq!(context.position.unwrap().line, 8);
+        assert!(error.to_string().contains("status 15500"));
+    }
 
-        // Test second credit card transaction
-        let cc_txn_2 = transactions
-            .iter()
-            .find(|t| t.fit_id == "TXN123457" && t.account_type.is_empty())
+    #[test]
+    fn test_server_error_statement_without_message() {
+        let error = QFX::from_str_with_options(STATEMENT_FAILED, &server_errors())
+            .err()
             .unwrap();
-        assert_eq!(cc_txn_2.trans_type, "CREDIT");
-        assert_eq!(cc_txn_2.trans_amount, 1000.00);
-        assert_eq!(cc_txn_2.name, "PAYROLL");
-        assert_eq!(cc_txn_2.memo, "DIRECT DEPOSIT");
-        assert_eq!(cc_txn_2.check_num, "");
-        assert_eq!(cc_txn_2.account_id, "4111222233334444");
-        assert_eq!(cc_txn_2.account_type, ""); // Credit card accounts have empty account_type
+        assert!(matches!(
+            &error,
+            QFXParsingError::ServerError { code: StatusCode::AccountNotFound, message, .. }
+                if message == "Account not found"
+        ));
+        assert_eq!(
+            error.context().unwrap().path,
+            "OFX/BANKMSGSRSV1/STMTTRNRS[0]/STATUS"
+        );
+    }
 
-        // Verify all transactions have account information
-        for transaction in &transactions {
-            assert!(
-                !transaction.account_id.is_empty(),
-                "Account ID should not be empty"
-            );
-            assert!(!transaction.fit_id.is_empty(), "FIT ID should not be empty");
-            assert!(!transaction.name.is_empty(), "Name should not be empty");
-        }
+    #[test]
+    fn test_server_errors_ignores_warnings() {
+        let input = SIGNON_FAILED.replace("<SEVERITY>ERROR", "<SEVERITY>WARN");
+        let (qfx, _) = QFX::from_str_with_options(&input, &server_errors()).unwrap();
+        assert!(qfx.sign_on_msg_srs_v1.is_some());
     }
 }
 
@@ -1168,7 +2949,7 @@ mod available_balance_tests {
         assert!(result.is_err());
         if let Err(e) = result {
             assert!(
-                matches!(e, QFXParsingError::MissingRequiredValue(_)),
+                matches!(e, QFXParsingError::MissingRequiredValue { .. }),
                 "Expected MissingRequiredValue error, got: {:?}",
                 e
             );
@@ -1179,14 +2960,12 @@ mod available_balance_tests {
     fn test_available_balance_parse_missing_date() {
         // Missing BALAMT and DTASOF
         let input = "<BALAMT>1234.56</AVAILBAL>";
-        let mut tokens = input
-            .split(['<', '>'].as_ref())
-            .filter(|x| !x.trim().is_empty());
+        let mut tokens = tokenize(input);
         let result = AvailableBalance::parse(&mut tokens);
         assert!(result.is_err());
         if let Err(e) = result {
             assert!(
-                matches!(e, QFXParsingError::MissingRequiredValue(_)),
+                matches!(e, QFXParsingError::MissingRequiredValue { .. }),
                 "Expected MissingRequiredValue error, got: {:?}",
                 e
             );
@@ -1199,9 +2978,7 @@ mod available_balance_tests {
         <BALAMT>1234.56\
         <DTASOF>20250725T143000Z\
     </AVAILBAL>";
-        let mut tokens = input
-            .split(['<', '>'].as_ref())
-            .filter(|x| !x.trim().is_empty());
+        let mut tokens = tokenize(input);
         let result = AvailableBalance::parse(&mut tokens);
 
         assert!(
@@ -1210,7 +2987,7 @@ mod available_balance_tests {
             result.err()
         );
         let avail_bal = result.unwrap();
-        assert_eq!(avail_bal.balance_amount, "1234.56");
+        assert_eq!(avail_bal.balance_amount, Amount::new(123456, 2));
         assert_eq!(
             avail_bal.dt_as_of,
             chrono::Utc
@@ -1229,14 +3006,12 @@ mod ledger_balance_tests {
     fn test_ledger_balance_parse_missing_amount() {
         // Missing BALAMT and DTASOF
         let input = "<DTASOF>20250725T143000Z</LEDGERBAL>";
-        let mut tokens = input
-            .split(['<', '>'].as_ref())
-            .filter(|x| !x.trim().is_empty());
+        let mut tokens = tokenize(input);
         let result = LedgerBal::parse(&mut tokens);
         assert!(result.is_err());
         if let Err(e) = result {
             assert!(
-                matches!(e, QFXParsingError::MissingRequiredValue(_)),
+                matches!(e, QFXParsingError::MissingRequiredValue { .. }),
                 "Expected MissingRequiredValue error, got: {:?}",
                 e
             );
@@ -1247,14 +3022,12 @@ mod ledger_balance_tests {
     fn test_ledger_balance_parse_missing_date() {
         // Missing BALAMT and DTASOF
         let input = "<BALAMT>1234.56</LEDGERBAL>";
-        let mut tokens = input
-            .split(['<', '>'].as_ref())
-            .filter(|x| !x.trim().is_empty());
+        let mut tokens = tokenize(input);
         let result = LedgerBal::parse(&mut tokens);
         assert!(result.is_err());
         if let Err(e) = result {
             assert!(
-                matches!(e, QFXParsingError::MissingRequiredValue(_)),
+                matches!(e, QFXParsingError::MissingRequiredValue { .. }),
                 "Expected MissingRequiredValue error, got: {:?}",
                 e
             );
@@ -1267,9 +3040,7 @@ mod ledger_balance_tests {
         <BALAMT>1234.56\
         <DTASOF>20250725T143000Z\
     </LEDGERBAL>";
-        let mut tokens = input
-            .split(['<', '>'].as_ref())
-            .filter(|x| !x.trim().is_empty());
+        let mut tokens = tokenize(input);
         let result = LedgerBal::parse(&mut tokens);
 
         assert!(
@@ -1278,7 +3049,7 @@ mod ledger_balance_tests {
             result.err()
         );
         let avail_bal = result.unwrap();
-        assert_eq!(avail_bal.balance_amount, "1234.56");
+        assert_eq!(avail_bal.balance_amount, Amount::new(123456, 2));
         assert_eq!(
             avail_bal.dt_as_of,
             chrono::Utc
@@ -1286,12 +3057,33 @@ mod ledger_balance_tests {
                 .unwrap()
         );
     }
+
+    #[test]
+    fn test_ledger_balance_parse_invalid_amount() {
+        let input = "<BALAMT>1.5e3<DTASOF>20250725T143000Z</LEDGERBAL>";
+        let mut tokens = tokenize(input);
+        let result = LedgerBal::parse(&mut tokens);
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::InvalidTransactionAmount { message, value, .. })
+                if value == "1.5e3" && message.contains("Invalid balance amount")
+        ));
+    }
+
+    #[test]
+    fn test_ledger_balance_keeps_scale() {
+        let input = "<BALAMT>-1200.50<DTASOF>20250725T143000Z</LEDGERBAL>";
+        let mut tokens = tokenize(input);
+        let ledger_bal = LedgerBal::parse(&mut tokens).unwrap();
+        assert_eq!(ledger_bal.balance_amount.to_string(), "-1200.50");
+    }
 }
 
 #[cfg(test)]
 mod banktranlist_tests {
     use super::*;
     use chrono::TimeZone;
+    use chrono::Utc;
 
     #[test]
     fn test_banktranlist_parse_valid() {
@@ -1330,8 +3122,14 @@ mod banktranlist_tests {
             Utc.with_ymd_and_hms(2025, 7, 26, 14, 30, 0).unwrap()
         );
         assert_eq!(banktranlist.transactions.len(), 2);
-        assert_eq!(banktranlist.transactions[0].trans_type, "DEBIT");
-        assert_eq!(banktranlist.transactions[1].trans_type, "CREDIT");
+        assert_eq!(
+            banktranlist.transactions[0].trans_type,
+            TransactionType::Debit
+        );
+        assert_eq!(
+            banktranlist.transactions[1].trans_type,
+            TransactionType::Credit
+        );
     }
 
     #[test]
@@ -1344,7 +3142,7 @@ mod banktranlist_tests {
         assert!(result.is_err());
         if let Err(e) = result {
             assert!(
-                matches!(e, QFXParsingError::MissingRequiredValue(_)),
+                matches!(e, QFXParsingError::MissingRequiredValue { .. }),
                 "Expected MissingRequiredValue error for missing DTSTART, got: {:?}",
                 e
             );
@@ -1361,7 +3159,7 @@ mod banktranlist_tests {
         assert!(result.is_err());
         if let Err(e) = result {
             assert!(
-                matches!(e, QFXParsingError::MissingRequiredValue(_)),
+                matches!(e, QFXParsingError::MissingRequiredValue { .. }),
                 "Expected MissingRequiredValue error for missing DTEND, got: {:?}",
                 e
             );
@@ -1379,7 +3177,7 @@ mod banktranlist_tests {
         assert!(result.is_err());
         if let Err(e) = result {
             assert!(
-                matches!(e, QFXParsingError::UnexpectedEOF(_)),
+                matches!(e, QFXParsingError::UnexpectedEOF { .. }),
                 "Expected UnexpectedEOF error for missing /BANKTRANLIST, got: {:?}",
                 e
             );
@@ -1387,6 +3185,66 @@ mod banktranlist_tests {
     }
 }
 
+#[cfg(test)]
+mod entity_tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_entities_named() {
+        assert_eq!(decode_entities("AT&amp;T"), "AT&T");
+        assert_eq!(decode_entities("B&lt;3 CAFE"), "B<3 CAFE");
+        assert_eq!(decode_entities("1 &gt; 0"), "1 > 0");
+        assert_eq!(decode_entities("A&nbsp;B"), "A\u{a0}B");
+    }
+
+    #[test]
+    fn test_decode_entities_numeric() {
+        assert_eq!(decode_entities("CAF&#233;"), "CAFé");
+        assert_eq!(decode_entities("CAF&#xE9;"), "CAFé");
+        assert_eq!(decode_entities("JOE&#8217;S"), "JOE’S");
+    }
+
+    #[test]
+    fn test_decode_entities_keeps_unknown() {
+        assert_eq!(decode_entities("A & B"), "A & B");
+        assert_eq!(decode_entities("A &foo; B"), "A &foo; B");
+        assert_eq!(decode_entities("A &#xZZ; B"), "A &#xZZ; B");
+        assert_eq!(decode_entities("AT&T&amp;"), "AT&T&");
+    }
+
+    #[test]
+    fn test_encode_entities_round_trip() {
+        let value = "AT&T B<3 > A\u{a0}B \"Joe's\"";
+        assert_eq!(
+            encode_entities(value),
+            "AT&amp;T B&lt;3 &gt; A\u{a0}B \"Joe's\""
+        );
+        assert_eq!(decode_entities(&encode_entities(value)), value);
+
+        for (entity, character) in ENTITIES {
+            let value = character.to_string();
+            assert_eq!(decode_entities(&encode_entities(&value)), value);
+            assert_eq!(decode_entities(entity), value);
+        }
+    }
+
+    #[test]
+    fn test_stmttrn_parse_decodes_entities() {
+        let input = "\
+            <TRNTYPE>DEBIT\
+            <DTPOSTED>20250725T143000Z\
+            <TRNAMT>-100.51\
+            <FITID>12345\
+            <NAME>AT&amp;T\
+            <MEMO>B&lt;3 CAFE\
+        </STMTTRN>";
+        let mut tokens = tokenize(input);
+        let stmttrn = Stmttrn::parse(&mut tokens).unwrap();
+        assert_eq!(stmttrn.name, Some("AT&T".to_string()));
+        assert_eq!(stmttrn.memo, Some("B<3 CAFE".to_string()));
+    }
+}
+
 #[cfg(test)]
 mod status_tests {
     use super::*;
@@ -1402,11 +3260,55 @@ mod status_tests {
         let result = Status::parse(&mut tokens);
         assert!(result.is_ok());
         let status = result.unwrap();
-        assert_eq!(status.code, "200");
-        assert_eq!(status.severity, "INFO");
+        assert_eq!(status.code, StatusCode::Other("200".to_string()));
+        assert_eq!(status.severity, Severity::Info);
+        assert_eq!(status.message, Some("Everything OK".to_string()));
+    }
+
+    #[test]
+    fn test_status_parse_valid_with_leaf_end_tags() {
+        let input = "\
+        <CODE>0</CODE>\
+        <SEVERITY>INFO</SEVERITY>\
+        <MESSAGE>Everything OK</MESSAGE>\
+    </STATUS>";
+        let mut tokens = tokenize(input);
+        let status = Status::parse(&mut tokens).unwrap();
+        assert_eq!(status.code, StatusCode::Success);
+        assert_eq!(status.severity, Severity::Info);
+        assert!(status.is_success());
         assert_eq!(status.message, Some("Everything OK".to_string()));
     }
 
+    #[test]
+    fn test_status_parse_error() {
+        let input = "\
+        <CODE>2003\
+        <SEVERITY>ERROR\
+        <MESSAGE>No such account\
+    </STATUS>";
+        let mut tokens = tokenize(input);
+        let status = Status::parse(&mut tokens).unwrap();
+        assert_eq!(status.code, StatusCode::AccountNotFound);
+        assert_eq!(status.severity, Severity::Error);
+        assert!(!status.is_success());
+    }
+
+    #[test]
+    fn test_status_is_success() {
+        let status = |code: &str, severity: &str| Status {
+            code: StatusCode::from(code),
+            severity: Severity::from(severity),
+            message: None,
+        };
+        assert!(status("0", "INFO").is_success());
+        assert!(status("1", "INFO").is_success());
+        assert!(status("0", "WARN").is_success());
+        assert!(!status("0", "ERROR").is_success());
+        assert!(!status("15500", "ERROR").is_success());
+        assert!(!status("2000", "INFO").is_success());
+    }
+
     #[test]
     fn test_status_parse_missing_code() {
         let input = "\
@@ -1417,7 +3319,7 @@ mod status_tests {
         let result = Status::parse(&mut tokens);
         assert!(result.is_err());
         if let Err(e) = result {
-            assert!(matches!(e, QFXParsingError::MissingRequiredValue(_)));
+            assert!(matches!(e, QFXParsingError::MissingRequiredValue { .. }));
         }
     }
 
@@ -1431,7 +3333,7 @@ mod status_tests {
         let result = Status::parse(&mut tokens);
         assert!(result.is_err());
         if let Err(e) = result {
-            assert!(matches!(e, QFXParsingError::MissingRequiredValue(_)));
+            assert!(matches!(e, QFXParsingError::MissingRequiredValue { .. }));
         }
     }
 }
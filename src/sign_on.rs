@@ -1,9 +1,9 @@
+use crate::OfxDateTime;
 use crate::Parseable;
 use crate::QFXParsingError;
 use crate::Status;
-use crate::parse_ofx_datetime;
-use chrono::DateTime;
-use chrono::Utc;
+use crate::lexer::TokenKind;
+use crate::lexer::TokenStream;
 
 // TODO: Require doc comments
 // TODO: Require clippy formatting
@@ -18,8 +18,8 @@ pub struct Sonrs {
     pub status: Option<Status>,
     pub fi: FinancialInstitution,
     pub bid: Option<String>,
-    pub dt_server: DateTime<Utc>,
-    pub dt_acctup: Option<String>,
+    pub dt_server: OfxDateTime,
+    pub dt_acctup: Option<OfxDateTime>,
     pub language: Option<String>,
     pub cookie: Option<String>,
     pub user_id: Option<String>,
@@ -32,37 +32,60 @@ pub struct FinancialInstitution {
 }
 
 impl<'a> Parseable<'a> for SignOnMsgSrsV1 {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &["SONRS"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
         let mut s_sonrs = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "SONRS" => {
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("SONRS") => {
                     s_sonrs = Some(Sonrs::parse(tokens)?);
                 }
-                "/SIGNONMSGSRSV1" => {
+                TokenKind::EndTag("SIGNONMSGSRSV1") => {
                     return Ok(Self {
-                        sonrs: s_sonrs.ok_or(QFXParsingError::MissingRequiredValue(
+                        sonrs: s_sonrs.ok_or(QFXParsingError::missing_value(
                             "Missing value SONRS in SIGNONMSGSRSV1".to_string(),
+                            "SONRS",
                         ))?,
                     });
                 }
                 _ => {
-                    // Error case, unknown token seen
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the SIGNONMSGSRSV1 type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!(
+                                "Found unexpected token {} in the SIGNONMSGSRSV1 type",
+                                token
+                            ),
+                            token,
+                            Self::TAGS,
+                            "SIGNONMSGSRSV1",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/SIGNONMSGSRSV1' token".to_string(),
-        ));
+            "/SIGNONMSGSRSV1",
+        ))
     }
 }
 
 impl<'a> Parseable<'a> for Sonrs {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &[
+        "STATUS",
+        "DTSERVER",
+        "DTACCTUP",
+        "LANGUAGE",
+        "FI",
+        "INTU.BID",
+        "INTU.USERID",
+        "SESSCOOKIE",
+    ];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
         let mut s_status = None;
         let mut s_fi = None;
         let mut s_bid = None;
@@ -71,82 +94,43 @@ impl<'a> Parseable<'a> for Sonrs {
         let mut s_language = None;
         let mut s_user_id = None;
         let mut s_cookie = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "STATUS" => {
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("STATUS") => {
                     s_status = Some(Status::parse(tokens)?);
                 }
-                "DTSERVER" => {
-                    if let Some(dt_server) = tokens.next() {
-                        s_dt_server = Some(parse_ofx_datetime(dt_server).map_err(|e| {
-                            QFXParsingError::UnexpectedDateFormat(format!(
-                                "Failed to parse DTSERVER date time: {}",
-                                e
-                            ))
-                        })?);
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the DTSERVER token".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("DTSERVER") => {
+                    s_dt_server = Some(tokens.datetime("DTSERVER")?);
                 }
-                "DTACCTUP" => {
-                    if let Some(dt_acctup) = tokens.next() {
-                        s_dt_acctup = Some(dt_acctup.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the DTACCTUP token".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("DTACCTUP") => {
+                    s_dt_acctup = Some(tokens.datetime("DTACCTUP")?);
                 }
-                "LANGUAGE" => {
-                    if let Some(language) = tokens.next() {
-                        s_language = Some(language.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the LANGUAGE token".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("LANGUAGE") => {
+                    s_language = Some(tokens.value("LANGUAGE")?);
                 }
-                "FI" => {
+                TokenKind::StartTag("FI") => {
                     s_fi = Some(FinancialInstitution::parse(tokens)?);
                 }
-                "INTU.BID" => {
-                    if let Some(bid) = tokens.next() {
-                        s_bid = Some(bid.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the INTU.BID token".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("INTU.BID") => {
+                    s_bid = Some(tokens.value("INTU.BID")?);
                 }
-                "INTU.USERID" => {
-                    if let Some(user_id) = tokens.next() {
-                        s_user_id = Some(user_id.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the INTU.BID token".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("INTU.USERID") => {
+                    s_user_id = Some(tokens.value("INTU.USERID")?);
                 }
-                "SESSCOOKIE" => {
-                    if let Some(cookie) = tokens.next() {
-                        s_cookie = Some(cookie.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the SESSCOOKIE token".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("SESSCOOKIE") => {
+                    s_cookie = Some(tokens.value("SESSCOOKIE")?);
                 }
-                "/SONRS" => {
+                TokenKind::EndTag("SONRS") => {
                     return Ok(Self {
                         status: s_status,
-                        fi: s_fi.ok_or(QFXParsingError::MissingRequiredValue(
+                        fi: s_fi.ok_or(QFXParsingError::missing_value(
                             "Financial Institution (FI) is required in SONRS".to_string(),
+                            "FI",
                         ))?,
                         bid: s_bid,
-                        dt_server: s_dt_server.ok_or(QFXParsingError::MissingRequiredValue(
+                        dt_server: s_dt_server.ok_or(QFXParsingError::missing_value(
                             "DTSERVER is required in SONRS".to_string(),
+                            "DTSERVER",
                         ))?,
                         dt_acctup: s_dt_acctup,
                         cookie: s_cookie,
@@ -155,65 +139,70 @@ impl<'a> Parseable<'a> for Sonrs {
                     });
                 }
                 _ => {
-                    // Unknown token
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the SONRS type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the SONRS type", token),
+                            token,
+                            Self::TAGS,
+                            "SONRS",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/SONRS' token".to_string(),
-        ));
+            "/SONRS",
+        ))
     }
 }
 
 impl<'a> Parseable<'a> for FinancialInstitution {
-    fn parse(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, QFXParsingError> {
+    const TAGS: &'static [&'static str] = &["ORG", "FID"];
+
+    fn parse(tokens: &mut TokenStream<'a>) -> Result<Self, QFXParsingError> {
         let mut s_org = None;
         let mut s_fid = None;
-        while let Some(contents) = tokens.next() {
-            match contents {
-                "ORG" => {
-                    if let Some(severity) = tokens.next() {
-                        s_org = Some(severity.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the ORG token".to_string(),
-                        ));
-                    }
+        while let Some(token) = tokens.next_token()? {
+            match token.kind {
+                TokenKind::StartTag("ORG") => {
+                    s_org = Some(tokens.value("ORG")?);
                 }
-                "FID" => {
-                    if let Some(fid) = tokens.next() {
-                        s_fid = Some(fid.to_string());
-                    } else {
-                        return Err(QFXParsingError::UnexpectedEOF(
-                            "Expected token following the FIT token".to_string(),
-                        ));
-                    }
+                TokenKind::StartTag("FID") => {
+                    s_fid = Some(tokens.value("FID")?);
                 }
-                "/FI" => {
+                TokenKind::EndTag("FI") => {
                     return Ok(Self {
-                        org: s_org.ok_or(QFXParsingError::MissingRequiredValue(
+                        org: s_org.ok_or(QFXParsingError::missing_value(
                             "Missing ORG in FI".to_string(),
+                            "ORG",
                         ))?,
-                        fid: s_fid.ok_or(QFXParsingError::MissingRequiredValue(
+                        fid: s_fid.ok_or(QFXParsingError::missing_value(
                             "Missing FID in FI".to_string(),
+                            "FID",
                         ))?,
                     });
                 }
                 _ => {
-                    return Err(QFXParsingError::UnexpectedToken(format!(
-                        "Found unexpected token {} in the FI type",
-                        contents.to_string()
-                    )));
+                    // Unknown token seen. This is an error unless unknown tags are being skipped
+                    tokens.skip_unknown(
+                        token,
+                        QFXParsingError::unknown_token(
+                            format!("Found unexpected token {} in the FI type", token),
+                            token,
+                            Self::TAGS,
+                            "FI",
+                        ),
+                    )?;
                 }
             }
         }
-        return Err(QFXParsingError::UnexpectedEOF(
+        Err(QFXParsingError::unexpected_eof(
             "Found unexpected EOF. Was still expecting the '/FI' token".to_string(),
-        ));
+            "/FI",
+        ))
     }
 }
 
@@ -225,29 +214,29 @@ mod financial_institution_tests {
     #[test]
     fn test_financial_institution_missing_org() {
         let input = "<FID> 1234 </FI>";
-        let mut tokens = tokenize(input).into_iter();
+        let mut tokens = tokenize(input);
         let result = FinancialInstitution::parse(&mut tokens);
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("Missing ORG")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("Missing ORG")
         ));
     }
 
     #[test]
     fn test_financial_institution_missing_fid() {
         let input = "<ORG> BANKORG </FI>";
-        let mut tokens = tokenize(input).into_iter();
+        let mut tokens = tokenize(input);
         let result = FinancialInstitution::parse(&mut tokens);
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("Missing FID")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("Missing FID")
         ));
     }
 
     #[test]
     fn test_financial_institution_valid() {
         let input = "<ORG>BANK ORG<FID>1234</FI>";
-        let mut tokens = tokenize(input).into_iter();
+        let mut tokens = tokenize(input);
         let result = FinancialInstitution::parse(&mut tokens).unwrap();
         assert_eq!(result.org, "BANK ORG");
         assert_eq!(result.fid, "1234");
@@ -275,11 +264,11 @@ mod sonrs_tests {
             <INTU.BID>3000\
             <INTU.USERID>userid\
             </SONRS>";
-        let mut tokens = tokenize(input).into_iter();
+        let mut tokens = tokenize(input);
         let result = Sonrs::parse(&mut tokens);
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(_))
+            Err(QFXParsingError::MissingRequiredValue { .. })
         ));
     }
 
@@ -301,11 +290,47 @@ mod sonrs_tests {
             <INTU.BID>3000\
             <INTU.USERID>userid\
             </SONRS>";
-        let mut tokens = tokenize(input).into_iter();
+        let mut tokens = tokenize(input);
         let result = Sonrs::parse(&mut tokens);
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(_))
+            Err(QFXParsingError::MissingRequiredValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sonrs_dtacctup() {
+        let input = "\
+            <DTSERVER>20250623105912\
+            <DTACCTUP>20250601[-5:EST]\
+            <FI>\
+            <ORG>W\
+            <FID>3\
+            </FI>\
+            </SONRS>";
+        let mut tokens = tokenize(input);
+        let result = Sonrs::parse(&mut tokens).unwrap();
+        let dt_acctup = result.dt_acctup.unwrap();
+        assert_eq!(
+            dt_acctup,
+            chrono::Utc.with_ymd_and_hms(2025, 6, 1, 5, 0, 0).unwrap()
+        );
+        assert_eq!(dt_acctup.precision(), crate::DateTimePrecision::Day);
+        assert_eq!(dt_acctup.timezone(), Some("EST"));
+    }
+
+    #[test]
+    fn test_sonrs_invalid_dtacctup() {
+        let input = "\
+            <DTSERVER>20250623105912\
+            <DTACCTUP>last tuesday\
+            </SONRS>";
+        let mut tokens = tokenize(input);
+        let result = Sonrs::parse(&mut tokens);
+        assert!(matches!(
+            result,
+            Err(QFXParsingError::UnexpectedDateFormat { message, value, .. })
+                if value == "last tuesday" && message.contains("Failed to parse datetime for DTACCTUP")
         ));
     }
 
@@ -327,14 +352,14 @@ mod sonrs_tests {
             <INTU.BID>3000\
             <INTU.USERID>userid\
             </SONRS>";
-        let mut tokens = tokenize(input).into_iter();
+        let mut tokens = tokenize(input);
         let result = Sonrs::parse(&mut tokens).unwrap();
         assert_eq!(result.fi.org, "W");
         assert_eq!(result.fi.fid, "3");
         assert_eq!(
             result.dt_server,
             chrono::Utc
-                .with_ymd_and_hms(2025, 6, 23, 10, 59, 12)
+                .with_ymd_and_hms(2025, 6, 23, 17, 59, 12)
                 .unwrap()
                 .with_nanosecond(14000000)
                 .unwrap()
@@ -346,6 +371,29 @@ mod sonrs_tests {
         assert_eq!(result.user_id.as_deref(), Some("userid"));
         assert!(result.dt_acctup.is_none());
     }
+
+    #[test]
+    fn test_sonrs_valid_with_leaf_end_tags() {
+        let input = "\
+            <STATUS>\
+            <CODE>0</CODE>\
+            <SEVERITY>INFO</SEVERITY>\
+            </STATUS>\
+            <DTSERVER>20250623105912</DTSERVER>\
+            <LANGUAGE>ENG</LANGUAGE>\
+            <FI>\
+            <ORG>W</ORG>\
+            <FID>3</FID>\
+            </FI>\
+            <INTU.BID>3000</INTU.BID>\
+            </SONRS>";
+        let mut tokens = tokenize(input);
+        let result = Sonrs::parse(&mut tokens).unwrap();
+        assert_eq!(result.fi.org, "W");
+        assert_eq!(result.fi.fid, "3");
+        assert_eq!(result.language.as_deref(), Some("ENG"));
+        assert_eq!(result.bid.as_deref(), Some("3000"));
+    }
 }
 
 #[cfg(test)]
@@ -356,11 +404,11 @@ mod sign_on_msg_srs_v1_tests {
     #[test]
     fn test_sign_on_msg_srs_v1_missing_sonrs() {
         let input = "</SIGNONMSGSRSV1>";
-        let mut tokens = tokenize(input).into_iter();
+        let mut tokens = tokenize(input);
         let result = SignOnMsgSrsV1::parse(&mut tokens);
         assert!(matches!(
             result,
-            Err(QFXParsingError::MissingRequiredValue(msg)) if msg.contains("Missing value SONRS")
+            Err(QFXParsingError::MissingRequiredValue { message: msg, .. }) if msg.contains("Missing value SONRS")
         ));
     }
 
@@ -384,7 +432,7 @@ mod sign_on_msg_srs_v1_tests {
             <INTU.USERID>userid\
             </SONRS>\
             </SIGNONMSGSRSV1>";
-        let mut tokens = tokenize(input).into_iter();
+        let mut tokens = tokenize(input);
         let result = SignOnMsgSrsV1::parse(&mut tokens).unwrap();
         assert_eq!(result.sonrs.fi.org, "W");
         assert_eq!(result.sonrs.fi.fid, "3");
@@ -0,0 +1,202 @@
+use std::fmt::Display;
+
+/// The CODE of a STATUS aggregate. Codes that are not listed here are kept in `Other` along
+/// with the value from the file.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StatusCode {
+    /// 0
+    Success,
+    /// 1
+    UpToDate,
+    /// 2000
+    GeneralError,
+    /// 2001
+    InvalidAccount,
+    /// 2002
+    GeneralAccountError,
+    /// 2003
+    AccountNotFound,
+    /// 2004
+    AccountClosed,
+    /// 2005
+    AccountNotAuthorized,
+    /// 2019
+    DuplicateRequest,
+    /// 2020
+    InvalidDate,
+    /// 10500
+    InvalidDateRange,
+    /// 15000
+    MustChangePassword,
+    /// 15500
+    SignonInvalid,
+    /// 15501
+    AccountInUse,
+    /// 15502
+    PasswordLockout,
+    Other(String),
+}
+
+/// The SEVERITY of a STATUS aggregate.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+    Other(String),
+}
+
+impl StatusCode {
+    /// The value of the code as written in an OFX file. Eg. 2003
+    pub fn as_str(&self) -> &str {
+        match self {
+            StatusCode::Success => "0",
+            StatusCode::UpToDate => "1",
+            StatusCode::GeneralError => "2000",
+            StatusCode::InvalidAccount => "2001",
+            StatusCode::GeneralAccountError => "2002",
+            StatusCode::AccountNotFound => "2003",
+            StatusCode::AccountClosed => "2004",
+            StatusCode::AccountNotAuthorized => "2005",
+            StatusCode::DuplicateRequest => "2019",
+            StatusCode::InvalidDate => "2020",
+            StatusCode::InvalidDateRange => "10500",
+            StatusCode::MustChangePassword => "15000",
+            StatusCode::SignonInvalid => "15500",
+            StatusCode::AccountInUse => "15501",
+            StatusCode::PasswordLockout => "15502",
+            StatusCode::Other(value) => value,
+        }
+    }
+
+    /// What the code means, for showing to a person. None for codes that are not known.
+    pub fn description(&self) -> Option<&'static str> {
+        let description = match self {
+            StatusCode::Success => "Success",
+            StatusCode::UpToDate => "Client is up to date",
+            StatusCode::GeneralError => "General error",
+            StatusCode::InvalidAccount => "Invalid account",
+            StatusCode::GeneralAccountError => "General account error",
+            StatusCode::AccountNotFound => "Account not found",
+            StatusCode::AccountClosed => "Account closed",
+            StatusCode::AccountNotAuthorized => "Account not authorized",
+            StatusCode::DuplicateRequest => "Duplicate request",
+            StatusCode::InvalidDate => "Invalid date",
+            StatusCode::InvalidDateRange => "Invalid date range",
+            StatusCode::MustChangePassword => "User must change their password",
+            StatusCode::SignonInvalid => "Signon invalid. The user ID or password is wrong",
+            StatusCode::AccountInUse => "Customer account is already in use",
+            StatusCode::PasswordLockout => "The user is locked out after too many failed signons",
+            StatusCode::Other(_) => return None,
+        };
+        Some(description)
+    }
+}
+
+impl From<&str> for StatusCode {
+    fn from(value: &str) -> Self {
+        // Codes are numbers, so leading zeros do not matter
+        let trimmed = value.trim();
+        let code = match trimmed.trim_start_matches('0') {
+            "" if !trimmed.is_empty() => "0",
+            x => x,
+        };
+        match code {
+            "0" => StatusCode::Success,
+            "1" => StatusCode::UpToDate,
+            "2000" => StatusCode::GeneralError,
+            "2001" => StatusCode::InvalidAccount,
+            "2002" => StatusCode::GeneralAccountError,
+            "2003" => StatusCode::AccountNotFound,
+            "2004" => StatusCode::AccountClosed,
+            "2005" => StatusCode::AccountNotAuthorized,
+            "2019" => StatusCode::DuplicateRequest,
+            "2020" => StatusCode::InvalidDate,
+            "10500" => StatusCode::InvalidDateRange,
+            "15000" => StatusCode::MustChangePassword,
+            "15500" => StatusCode::SignonInvalid,
+            "15501" => StatusCode::AccountInUse,
+            "15502" => StatusCode::PasswordLockout,
+            _ => StatusCode::Other(value.to_string()),
+        }
+    }
+}
+
+impl Display for StatusCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Severity {
+    /// The value of the severity as written in an OFX file. Eg. ERROR
+    pub fn as_str(&self) -> &str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Warn => "WARN",
+            Severity::Error => "ERROR",
+            Severity::Other(value) => value,
+        }
+    }
+}
+
+impl From<&str> for Severity {
+    fn from(value: &str) -> Self {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "INFO" => Severity::Info,
+            "WARN" => Severity::Warn,
+            "ERROR" => Severity::Error,
+            _ => Severity::Other(value.to_string()),
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod status_code_tests {
+    use super::*;
+
+    #[test]
+    fn test_status_code_from_str() {
+        assert_eq!(StatusCode::from("0"), StatusCode::Success);
+        assert_eq!(StatusCode::from("000"), StatusCode::Success);
+        assert_eq!(StatusCode::from("2000"), StatusCode::GeneralError);
+        assert_eq!(StatusCode::from("2003"), StatusCode::AccountNotFound);
+        assert_eq!(StatusCode::from("10500"), StatusCode::InvalidDateRange);
+        assert_eq!(StatusCode::from("15500"), StatusCode::SignonInvalid);
+        assert_eq!(
+            StatusCode::from("12345"),
+            StatusCode::Other("12345".to_string())
+        );
+        assert_eq!(StatusCode::from(""), StatusCode::Other("".to_string()));
+    }
+
+    #[test]
+    fn test_status_code_description() {
+        assert_eq!(StatusCode::Success.description(), Some("Success"));
+        assert_eq!(
+            StatusCode::AccountNotFound.description(),
+            Some("Account not found")
+        );
+        assert_eq!(StatusCode::AccountNotFound.to_string(), "2003");
+        assert_eq!(StatusCode::Other("7".to_string()).description(), None);
+        assert_eq!(StatusCode::Other("7".to_string()).to_string(), "7");
+    }
+
+    #[test]
+    fn test_severity_from_str() {
+        assert_eq!(Severity::from("INFO"), Severity::Info);
+        assert_eq!(Severity::from("WARN"), Severity::Warn);
+        assert_eq!(Severity::from("error"), Severity::Error);
+        assert_eq!(
+            Severity::from("FATAL"),
+            Severity::Other("FATAL".to_string())
+        );
+        assert_eq!(Severity::Error.to_string(), "ERROR");
+    }
+}
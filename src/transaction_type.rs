@@ -0,0 +1,159 @@
+use std::fmt::Display;
+
+/// The kind of a transaction, from the TRNTYPE element of a STMTTRN.
+///
+/// Types that are not in the OFX spec are kept in `Other` along with the value from the file, so
+/// nothing is lost when a financial institution uses its own types.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TransactionType {
+    /// Generic credit
+    Credit,
+    /// Generic debit
+    Debit,
+    /// Interest earned or paid
+    Int,
+    /// Dividend
+    Div,
+    /// Financial institution fee
+    Fee,
+    /// Service charge
+    SrvChg,
+    /// Deposit
+    Dep,
+    /// ATM debit or credit
+    Atm,
+    /// Point of sale debit or credit
+    Pos,
+    /// Transfer
+    Xfer,
+    /// Check
+    Check,
+    /// Electronic payment
+    Payment,
+    /// Cash withdrawal
+    Cash,
+    /// Direct deposit
+    DirectDep,
+    /// Merchant initiated debit
+    DirectDebit,
+    /// Repeating payment or standing order
+    RepeatPmt,
+    /// Only valid in a pending transaction
+    Hold,
+    /// OTHER, the catch-all type from the OFX spec
+    OtherType,
+    /// A type that is not in the OFX spec
+    Other(String),
+}
+
+impl TransactionType {
+    /// The value of the type as written in an OFX file. Eg. DIRECTDEP
+    pub fn as_str(&self) -> &str {
+        match self {
+            TransactionType::Credit => "CREDIT",
+            TransactionType::Debit => "DEBIT",
+            TransactionType::Int => "INT",
+            TransactionType::Div => "DIV",
+            TransactionType::Fee => "FEE",
+            TransactionType::SrvChg => "SRVCHG",
+            TransactionType::Dep => "DEP",
+            TransactionType::Atm => "ATM",
+            TransactionType::Pos => "POS",
+            TransactionType::Xfer => "XFER",
+            TransactionType::Check => "CHECK",
+            TransactionType::Payment => "PAYMENT",
+            TransactionType::Cash => "CASH",
+            TransactionType::DirectDep => "DIRECTDEP",
+            TransactionType::DirectDebit => "DIRECTDEBIT",
+            TransactionType::RepeatPmt => "REPEATPMT",
+            TransactionType::Hold => "HOLD",
+            TransactionType::OtherType => "OTHER",
+            TransactionType::Other(value) => value,
+        }
+    }
+
+    /// True for the types defined by the OFX spec, including OTHER.
+    pub fn is_known(&self) -> bool {
+        !matches!(self, TransactionType::Other(_))
+    }
+}
+
+impl From<&str> for TransactionType {
+    fn from(value: &str) -> Self {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "CREDIT" => TransactionType::Credit,
+            "DEBIT" => TransactionType::Debit,
+            "INT" => TransactionType::Int,
+            "DIV" => TransactionType::Div,
+            "FEE" => TransactionType::Fee,
+            "SRVCHG" => TransactionType::SrvChg,
+            "DEP" => TransactionType::Dep,
+            "ATM" => TransactionType::Atm,
+            "POS" => TransactionType::Pos,
+            "XFER" => TransactionType::Xfer,
+            "CHECK" => TransactionType::Check,
+            "PAYMENT" => TransactionType::Payment,
+            "CASH" => TransactionType::Cash,
+            "DIRECTDEP" => TransactionType::DirectDep,
+            "DIRECTDEBIT" => TransactionType::DirectDebit,
+            "REPEATPMT" => TransactionType::RepeatPmt,
+            "HOLD" => TransactionType::Hold,
+            "OTHER" => TransactionType::OtherType,
+            _ => TransactionType::Other(value.to_string()),
+        }
+    }
+}
+
+impl Display for TransactionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod transaction_type_tests {
+    use super::*;
+
+    #[test]
+    fn test_transaction_type_round_trip() {
+        let types = [
+            "CREDIT",
+            "DEBIT",
+            "INT",
+            "DIV",
+            "FEE",
+            "SRVCHG",
+            "DEP",
+            "ATM",
+            "POS",
+            "XFER",
+            "CHECK",
+            "PAYMENT",
+            "CASH",
+            "DIRECTDEP",
+            "DIRECTDEBIT",
+            "REPEATPMT",
+            "HOLD",
+            "OTHER",
+        ];
+        for value in types {
+            let trans_type = TransactionType::from(value);
+            assert!(trans_type.is_known(), "{} should be known", value);
+            assert_eq!(trans_type.to_string(), value);
+        }
+        assert_eq!(
+            TransactionType::from("DIRECTDEP"),
+            TransactionType::DirectDep
+        );
+        assert_eq!(TransactionType::from("debit"), TransactionType::Debit);
+        assert_eq!(TransactionType::from("OTHER"), TransactionType::OtherType);
+    }
+
+    #[test]
+    fn test_transaction_type_unknown() {
+        let trans_type = TransactionType::from("DEBT");
+        assert_eq!(trans_type, TransactionType::Other("DEBT".to_string()));
+        assert!(!trans_type.is_known());
+        assert_eq!(trans_type.as_str(), "DEBT");
+    }
+}